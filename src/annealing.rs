@@ -0,0 +1,178 @@
+use super::planner::ScanningPlan;
+use super::search::{LibraryOrder, MoveKind};
+use num_format::{Locale, ToFormattedString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+pub enum Cooling {
+    /// Temperature decreases linearly to zero over the budget
+    Linear,
+    /// Temperature is multiplied by a factor at every iteration
+    Geometric(f64),
+    /// Temperature decreases exponentially to a final value over the budget
+    Exponential(f64),
+}
+
+impl FromStr for Cooling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let name = parts.next().unwrap_or_default();
+        let value = parts
+            .next()
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|err| format!("{}: {}", err, value))
+            })
+            .transpose()?;
+        match (name, value) {
+            ("linear", None) => Ok(Cooling::Linear),
+            ("geometric", Some(factor)) => Ok(Cooling::Geometric(factor)),
+            ("exponential", Some(final_temp)) => {
+                Ok(Cooling::Exponential(final_temp))
+            }
+            _ => Err(format!("Invalid cooling schedule: {}", s)),
+        }
+    }
+}
+
+pub struct Annealer {
+    start_temp: f64,
+    cooling: Cooling,
+    moves: Vec<MoveKind>,
+    iterations: usize,
+    time_limit: Option<Duration>,
+    seed: u64,
+}
+
+impl Annealer {
+    pub fn new(
+        start_temp: f64,
+        cooling: Cooling,
+        moves: Vec<MoveKind>,
+        iterations: usize,
+        time_limit: Option<Duration>,
+        seed: u64,
+    ) -> Self {
+        Self {
+            start_temp,
+            cooling,
+            moves,
+            iterations,
+            time_limit,
+            seed,
+        }
+    }
+
+    /// Runs simulated annealing over the library order of a plan and returns
+    /// the best plan found. Worse moves are accepted with probability
+    /// `exp(delta / temperature)`.
+    pub fn anneal<'a>(&self, plan: &ScanningPlan<'a>) -> ScanningPlan<'a> {
+        let mut order = LibraryOrder::new(plan);
+        let mut best_order = order.order().to_vec();
+        let mut best_score = order.score();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let start_time = Instant::now();
+        let mut temp = self.start_temp;
+        let mut accepted = 0;
+        let mut iteration = 0;
+        while iteration < self.iterations {
+            let progress = self.progress(iteration, start_time.elapsed());
+            if progress >= 1_f64 {
+                break;
+            }
+            iteration += 1;
+            temp = match self.cooling {
+                Cooling::Linear => self.start_temp * (1_f64 - progress),
+                Cooling::Geometric(factor) => temp * factor,
+                Cooling::Exponential(final_temp) => {
+                    self.start_temp
+                        * (final_temp / self.start_temp).powf(progress)
+                }
+            };
+
+            let mv = match order.random_move(&mut rng, &self.moves) {
+                Some(mv) => mv,
+                None => continue,
+            };
+            let delta = order.evaluate(mv) as f64 - order.score() as f64;
+            if delta >= 0_f64
+                || (temp > 0_f64 && rng.gen::<f64>() < (delta / temp).exp())
+            {
+                order.apply(mv);
+                accepted += 1;
+                if order.score() > best_score {
+                    best_score = order.score();
+                    best_order = order.order().to_vec();
+                }
+            }
+        }
+        println!(
+            "Annealing: {} moves accepted in {} iterations, \
+             final temperature {:0.4}, best score {}",
+            accepted.to_formatted_string(&Locale::en),
+            iteration.to_formatted_string(&Locale::en),
+            temp,
+            best_score.to_formatted_string(&Locale::en)
+        );
+
+        let improved =
+            LibraryOrder::from_order(plan.task(), best_order).to_plan();
+        match plan.score() {
+            Ok((score, _, _)) if score >= best_score => plan.clone(),
+            _ => improved,
+        }
+    }
+
+    fn progress(&self, iteration: usize, elapsed: Duration) -> f64 {
+        let by_iterations = if self.iterations == usize::MAX {
+            0_f64
+        } else {
+            iteration as f64 / self.iterations as f64
+        };
+        let by_time = self
+            .time_limit
+            .map_or(0_f64, |limit| elapsed.as_secs_f64() / limit.as_secs_f64());
+        by_iterations.max(by_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::example_task;
+    use crate::search::ALL_MOVES;
+
+    #[test]
+    fn parses_cooling_schedules() {
+        assert!(matches!("linear".parse(), Ok(Cooling::Linear)));
+        assert!(matches!(
+            "geometric:0.5".parse(),
+            Ok(Cooling::Geometric(factor)) if factor == 0.5
+        ));
+        assert!("linear:1".parse::<Cooling>().is_err());
+        assert!("exponential".parse::<Cooling>().is_err());
+    }
+
+    #[test]
+    fn finds_optimal_example_plan() {
+        let task = example_task();
+        let plan = LibraryOrder::from_order(&task, vec![0]).to_plan();
+        for cooling in [Cooling::Linear, Cooling::Geometric(0.99)] {
+            let annealer = Annealer::new(
+                10_f64,
+                cooling,
+                ALL_MOVES.to_vec(),
+                200,
+                None,
+                0,
+            );
+            let annealed = annealer.anneal(&plan);
+            assert_eq!(annealed.score().map(|(score, _, _)| score), Ok(21));
+        }
+    }
+}
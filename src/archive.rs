@@ -0,0 +1,190 @@
+use super::planner::ScanningPlan;
+use super::validator::{validate, Violation};
+use super::ScanningTask;
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, read_dir, read_to_string, write};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How a submission was produced, stored in a `.meta` file next to it
+pub struct RunMetadata {
+    pub solver: String,
+    pub parameters: String,
+    pub seed: Option<u64>,
+    pub runtime: Duration,
+}
+
+impl Display for RunMetadata {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "solver: {}", self.solver)?;
+        writeln!(f, "parameters: {}", self.parameters)?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed: {}", seed)?;
+        }
+        writeln!(f, "runtime: {:0.3}s", self.runtime.as_secs_f64())
+    }
+}
+
+/// Name of the instance an input or submission file belongs to: the part of
+/// the file name before the first underscore, e.g. `b` for `b_read_on.txt`
+/// and `b_5822900.txt`.
+pub fn instance_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    stem.split('_').next().unwrap_or_default().to_string()
+}
+
+/// Highest-scoring valid submission for an instance among the files of a
+/// directory, with its judge score. Invalid and unreadable files are
+/// ignored.
+pub fn best_submission(
+    task: &ScanningTask,
+    dir: &Path,
+    instance: &str,
+) -> Option<(u64, PathBuf)> {
+    let entries = read_dir(dir).ok()?;
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "txt")
+                && instance_name(path) == instance
+        })
+        .filter_map(|path| {
+            let input = read_to_string(&path).ok()?;
+            if validate(task, &input).iter().any(Violation::is_error) {
+                return None;
+            }
+            let plan = ScanningPlan::parse(task, &input).ok()?;
+            Some((plan.judge_score().0, path))
+        })
+        .max()
+}
+
+/// Saves a plan as `<instance>_<score>.txt` in the archive directory, with
+/// its metadata in a sidecar file, only if it scores higher than the best
+/// valid submission for the instance already there. Returns the path of the
+/// new submission, if saved.
+pub fn save_submission(
+    plan: &ScanningPlan,
+    dir: &Path,
+    instance: &str,
+    metadata: &RunMetadata,
+) -> io::Result<Option<PathBuf>> {
+    let score = plan.judge_score().0;
+    if let Some((best, _)) = best_submission(plan.task(), dir, instance) {
+        if best >= score {
+            return Ok(None);
+        }
+    }
+    create_dir_all(dir)?;
+    let path = dir.join(format!("{}_{}.txt", instance, score));
+    write(&path, plan.to_string())?;
+    write(path.with_extension("meta"), metadata.to_string())?;
+    Ok(Some(path))
+}
+
+/// Best submission for every instance with an input file in the inputs
+/// directory, sorted by instance, with its judge score. Submissions are
+/// validated and judged again rather than trusting the scores in their file
+/// names, and instances without a valid submission are left out.
+pub fn best_submissions(
+    dir: &Path,
+    inputs: &Path,
+) -> io::Result<Vec<(String, u64, PathBuf)>> {
+    let mut best = Vec::new();
+    for entry in read_dir(inputs)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+        let task =
+            read_to_string(&path)?
+                .parse::<ScanningTask>()
+                .map_err(|err| {
+                    let message = format!("{}: {}", path.display(), err);
+                    io::Error::new(io::ErrorKind::InvalidData, message)
+                })?;
+        let instance = instance_name(&path);
+        if let Some((score, path)) = best_submission(&task, dir, &instance) {
+            best.push((instance, score, path));
+        }
+    }
+    best.sort_unstable();
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, EXAMPLE_SUBMISSION};
+    use std::fs::remove_dir_all;
+
+    /// Empty scratch directory, unique to a test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "archive-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_instances() {
+        for (path, instance) in [
+            ("input/b_read_on.txt", "b"),
+            ("output/b_5822900.txt", "b"),
+            ("output/a.txt", "a"),
+            ("e_5044007_error_library_602_exceeds.txt", "e"),
+            ("", ""),
+        ] {
+            assert_eq!(instance_name(Path::new(path)), instance);
+        }
+    }
+
+    #[test]
+    fn finds_best_valid_submission() {
+        let task = example_task();
+        let dir = scratch_dir("best");
+        write(dir.join("a_21.txt"), EXAMPLE_SUBMISSION).unwrap();
+        write(dir.join("a_17.txt"), "1\n0 5\n4 3 2 1 0\n").unwrap();
+        // Claims a higher score, but signs up a library twice
+        write(dir.join("a_99.txt"), "2\n0 1\n4\n0 1\n3\n").unwrap();
+        write(dir.join("b_99.txt"), EXAMPLE_SUBMISSION).unwrap();
+        write(dir.join("a_21.meta"), "solver: greedy\n").unwrap();
+        let best = best_submission(&task, &dir, "a");
+        assert_eq!(best, Some((21, dir.join("a_21.txt"))));
+        assert_eq!(best_submission(&task, &dir, "c"), None);
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saves_only_better_submissions() {
+        let task = example_task();
+        let dir = scratch_dir("save");
+        let metadata = RunMetadata {
+            solver: "greedy".to_string(),
+            parameters: String::new(),
+            seed: Some(1),
+            runtime: Duration::from_millis(5),
+        };
+        let partial = ScanningPlan::parse(&task, "1\n0 1\n4\n").unwrap();
+        let path = save_submission(&partial, &dir, "a", &metadata).unwrap();
+        assert_eq!(path, Some(dir.join("a_5.txt")));
+        assert!(dir.join("a_5.meta").exists());
+        assert_eq!(
+            save_submission(&partial, &dir, "a", &metadata).unwrap(),
+            None
+        );
+        let plan = ScanningPlan::parse(&task, EXAMPLE_SUBMISSION).unwrap();
+        let path = save_submission(&plan, &dir, "a", &metadata).unwrap();
+        assert_eq!(path, Some(dir.join("a_21.txt")));
+        assert_eq!(
+            read_to_string(dir.join("a_21.txt")).unwrap(),
+            EXAMPLE_SUBMISSION
+        );
+        remove_dir_all(&dir).unwrap();
+    }
+}
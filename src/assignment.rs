@@ -0,0 +1,181 @@
+use super::planner::ScanningPlan;
+use super::{Library, ScanningTask};
+use std::collections::VecDeque;
+
+const UNASSIGNED: usize = usize::MAX;
+
+/// Computes the score-maximising book assignment for the library order of a
+/// plan. Libraries that end up scanning no books are dropped, which gives the
+/// following libraries more days, and the assignment is computed again.
+pub fn optimal_assignment<'a>(plan: &ScanningPlan<'a>) -> ScanningPlan<'a> {
+    let mut order = plan
+        .libraries()
+        .map(|(library, _)| library)
+        .collect::<Vec<_>>();
+    loop {
+        let assigned = assign_books(plan.task(), &order);
+        if assigned.iter().all(|books| !books.is_empty()) {
+            let mut optimal = ScanningPlan::new(plan.task());
+            for (library, books) in order.into_iter().zip(assigned) {
+                optimal.add_library(library, books);
+            }
+            return optimal;
+        }
+        order = order
+            .into_iter()
+            .zip(assigned)
+            .filter(|(_, books)| !books.is_empty())
+            .map(|(library, _)| library)
+            .collect();
+    }
+}
+
+/// Assigns books to a fixed sequence of libraries so that the total score is
+/// maximal, given that each library can scan at most
+/// `(D - finish_of_signup) * scan_rate` books.
+///
+/// The sets of books that can be scanned together form a transversal matroid,
+/// so taking books in decreasing score order and keeping each one that can
+/// still be fitted in, possibly by moving already assigned books between
+/// libraries along an augmenting path, yields an optimal assignment. Once a
+/// search for an augmenting path fails, every library it visited is full and
+/// can never be reached by a later successful search, so it is skipped.
+pub fn assign_books(task: &ScanningTask, order: &[&Library]) -> Vec<Vec<u32>> {
+    let mut capacities = Vec::with_capacity(order.len());
+    let mut days_left = task.days;
+    for library in order.iter() {
+        days_left = days_left.saturating_sub(library.signup_days);
+        capacities.push((days_left * library.scan_rate) as usize);
+    }
+
+    let compact = task.compact();
+    let num_books = compact.num_books();
+    let mut book_libraries: Vec<Vec<usize>> = vec![Vec::new(); num_books];
+    for (index, library) in order.iter().enumerate() {
+        if capacities[index] == 0 {
+            continue;
+        }
+        for book_id in library.books.iter() {
+            book_libraries[*book_id as usize].push(index);
+        }
+    }
+    let mut candidates = (0..num_books as u32)
+        .filter(|book_id| !book_libraries[*book_id as usize].is_empty())
+        .collect::<Vec<_>>();
+    compact.sort_books(&mut candidates);
+
+    let mut owners = vec![UNASSIGNED; num_books];
+    let mut positions = vec![0; num_books];
+    let mut assigned: Vec<Vec<u32>> = vec![Vec::new(); order.len()];
+    let mut full = vec![false; order.len()];
+    let mut visited = vec![0; order.len()];
+    let mut reached_from = vec![0_u32; order.len()];
+    let mut visited_list = Vec::new();
+    let mut queue = VecDeque::new();
+    let mut stamp = 0;
+    for book_id in candidates {
+        stamp += 1;
+        queue.clear();
+        queue.push_back(book_id);
+        visited_list.clear();
+        let mut found = None;
+        'search: while let Some(current) = queue.pop_front() {
+            for index in book_libraries[current as usize].iter().cloned() {
+                if full[index] || visited[index] == stamp {
+                    continue;
+                }
+                if assigned[index].len() < capacities[index] {
+                    found = Some((current, index));
+                    break 'search;
+                }
+                visited[index] = stamp;
+                visited_list.push(index);
+                reached_from[index] = current;
+                for other in assigned[index].iter() {
+                    if *other != current {
+                        queue.push_back(*other);
+                    }
+                }
+            }
+        }
+
+        match found {
+            Some((mut current, mut index)) => loop {
+                // Move current book into library, freeing its previous slot
+                let previous = owners[current as usize];
+                if previous != UNASSIGNED {
+                    let pos = positions[current as usize];
+                    assigned[previous].swap_remove(pos);
+                    if let Some(moved) = assigned[previous].get(pos) {
+                        positions[*moved as usize] = pos;
+                    }
+                }
+                owners[current as usize] = index;
+                positions[current as usize] = assigned[index].len();
+                assigned[index].push(current);
+                if current == book_id {
+                    break;
+                }
+                index = previous;
+                current = reached_from[index];
+            },
+            None => {
+                for index in visited_list.iter() {
+                    full[*index] = true;
+                }
+            }
+        }
+    }
+
+    assigned
+        .into_iter()
+        .map(|mut book_ids| {
+            compact.sort_books(&mut book_ids);
+            book_ids
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exact::ExactSolver;
+    use crate::fixtures::{EXAMPLE, SHARED_BOOKS};
+
+    #[test]
+    fn moves_books_to_fit_more() {
+        // Taking the two best books in the first library leaves nothing for
+        // the second one, which only holds the best book
+        let task: ScanningTask =
+            "3 2 3\n5 4 1\n3 1 1\n0 1 2\n1 1 1\n0\n".parse().unwrap();
+        let order = task.libraries.iter().collect::<Vec<_>>();
+        assert_eq!(assign_books(&task, &order), vec![vec![1, 2], vec![0]]);
+    }
+
+    #[test]
+    fn matches_exact_solver() {
+        for input in [EXAMPLE, "3 2 3\n5 4 1\n3 1 1\n0 1 2\n1 1 1\n0\n"] {
+            let task: ScanningTask = input.parse().unwrap();
+            let (exact, certificate) = ExactSolver::new(&task, 1000).solve();
+            assert!(certificate.is_optimal());
+            let optimal = optimal_assignment(&exact);
+            assert_eq!(
+                optimal.score().map(|(score, _, _)| score),
+                Ok(certificate.score)
+            );
+        }
+    }
+
+    #[test]
+    fn drops_libraries_without_books() {
+        // The third library scans one more book once the second is dropped
+        let task: ScanningTask = SHARED_BOOKS.parse().unwrap();
+        let mut plan = ScanningPlan::new(&task);
+        for library in task.libraries.iter() {
+            plan.add_library(library, library.books.clone());
+        }
+        let optimal = optimal_assignment(&plan);
+        assert_eq!(optimal.to_string(), "2\n0 1\n0\n2 2\n1 2\n");
+        assert_eq!(optimal.score().map(|(score, _, _)| score), Ok(9));
+    }
+}
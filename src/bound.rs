@@ -0,0 +1,124 @@
+use super::{Library, ScanningTask};
+
+/// Above this many cells the knapsack is solved fractionally instead
+const MAX_KNAPSACK_CELLS: u64 = 4_000_000_000;
+
+impl ScanningTask {
+    /// Upper bound on the score of any plan: the smallest of the total book
+    /// score and the sign-up time knapsack bound. Only computed once.
+    pub fn upper_bound(&self) -> u64 {
+        *self
+            .upper_bound
+            .get_or_init(|| self.total_book_score().min(self.knapsack_bound()))
+    }
+
+    /// Every signed-up library must finish signing up before the deadline, so
+    /// the libraries of a plan fit in a knapsack of `D - 1` sign-up days, and
+    /// no library can scan more than its best `(D - signup_days) * scan_rate`
+    /// books. The best knapsack of libraries valued that way bounds the score,
+    /// since books shared by several libraries are only counted more often.
+    pub fn knapsack_bound(&self) -> u64 {
+        if self.days == 0 {
+            return 0;
+        }
+        let capacity = self.days - 1;
+        let items = self
+            .libraries
+            .iter()
+            .filter(|library| library.signup_days < self.days)
+            .map(|library| (library.signup_days, self.library_bound(library)))
+            .filter(|(_, value)| *value > 0)
+            .collect::<Vec<_>>();
+
+        if items.len() as u64 * (capacity + 1) > MAX_KNAPSACK_CELLS {
+            return fractional_knapsack(&items, capacity);
+        }
+        let mut best = vec![0_u64; capacity as usize + 1];
+        for (weight, value) in items {
+            let weight = weight as usize;
+            for used in (weight..best.len()).rev() {
+                best[used] = best[used].max(best[used - weight] + value);
+            }
+        }
+        best.last().cloned().unwrap_or_default()
+    }
+
+    /// Score of the best books a library could scan if signed up first
+    fn library_bound(&self, library: &Library) -> u64 {
+        let max_scans = (self.days - library.signup_days) * library.scan_rate;
+        let compact = self.compact();
+        library
+            .books
+            .iter()
+            .take(max_scans as usize)
+            .map(|book_id| compact.book_score(*book_id))
+            .sum()
+    }
+}
+
+/// Dantzig bound: fills the knapsack by decreasing value per unit of weight,
+/// taking a fraction of the first item that does not fit.
+fn fractional_knapsack(items: &[(u64, u64)], capacity: u64) -> u64 {
+    let mut items = items.to_vec();
+    sort_by_density(&mut items);
+    fill_knapsack(items, capacity)
+}
+
+/// Sorts `(weight, value)` items by decreasing value per unit of weight
+pub(crate) fn sort_by_density(items: &mut [(u64, u64)]) {
+    items.sort_unstable_by(|(w1, v1), (w2, v2)| {
+        (*v2 as u128 * *w1 as u128).cmp(&(*v1 as u128 * *w2 as u128))
+    });
+}
+
+/// Dantzig bound of items already sorted by density
+pub(crate) fn fill_knapsack<I>(items: I, capacity: u64) -> u64
+where
+    I: IntoIterator<Item = (u64, u64)>,
+{
+    let mut room = capacity;
+    let mut bound = 0;
+    for (weight, value) in items {
+        if weight <= room {
+            room -= weight;
+            bound += value;
+        } else {
+            bound += (value * room).div_ceil(weight);
+            break;
+        }
+    }
+    bound
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exact::ExactSolver;
+    use crate::fixtures::{example_task, EXAMPLE, SHARED_BOOKS};
+
+    #[test]
+    fn bounds_optimal_score() {
+        for input in [EXAMPLE, SHARED_BOOKS] {
+            let task: ScanningTask = input.parse().unwrap();
+            let (_, certificate) = ExactSolver::new(&task, 1000).solve();
+            assert!(task.knapsack_bound() >= certificate.score);
+            assert!(task.upper_bound() >= certificate.score);
+        }
+    }
+
+    #[test]
+    fn example_bound_is_tight() {
+        let task = example_task();
+        assert_eq!(task.upper_bound(), 21);
+    }
+
+    #[test]
+    fn fills_knapsack_fractionally() {
+        let mut items = vec![(3, 9), (2, 10), (1, 1)];
+        sort_by_density(&mut items);
+        assert_eq!(items, vec![(2, 10), (3, 9), (1, 1)]);
+        assert_eq!(fill_knapsack(items.iter().cloned(), 4), 16);
+        assert_eq!(fill_knapsack(items.iter().cloned(), 6), 20);
+        assert_eq!(fractional_knapsack(&items, 0), 0);
+    }
+}
@@ -0,0 +1,121 @@
+use std::cmp::Reverse;
+
+/// Set of book ids stored as a bitset
+#[derive(Clone)]
+pub struct BookSet {
+    bits: Vec<u64>,
+}
+
+impl BookSet {
+    pub fn new(num_books: usize) -> Self {
+        Self {
+            bits: vec![0; num_books.div_ceil(64)],
+        }
+    }
+
+    pub fn contains(&self, book_id: u32) -> bool {
+        self.bits[book_id as usize / 64] & (1 << (book_id % 64)) != 0
+    }
+
+    /// Adds a book, returning whether it was not in the set
+    pub fn insert(&mut self, book_id: u32) -> bool {
+        let word = &mut self.bits[book_id as usize / 64];
+        let mask = 1 << (book_id % 64);
+        let added = *word & mask == 0;
+        *word |= mask;
+        added
+    }
+
+    pub fn remove(&mut self, book_id: u32) {
+        self.bits[book_id as usize / 64] &= !(1 << (book_id % 64));
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+    }
+}
+
+/// Index-based view of the books of a task, addressed by id
+pub struct CompactTask {
+    pub book_scores: Vec<u64>,
+    book_libraries: Vec<Vec<u32>>,
+}
+
+impl CompactTask {
+    pub(crate) fn new(book_scores: Vec<u64>) -> Self {
+        let book_libraries = vec![Vec::new(); book_scores.len()];
+        Self {
+            book_scores,
+            book_libraries,
+        }
+    }
+
+    /// Records the books a library holds in the book-to-library index
+    pub(crate) fn add_library(&mut self, id: u32, books: &[u32]) {
+        for book_id in books.iter() {
+            self.book_libraries[*book_id as usize].push(id);
+        }
+    }
+
+    pub fn num_books(&self) -> usize {
+        self.book_scores.len()
+    }
+
+    pub fn book_score(&self, book_id: u32) -> u64 {
+        self.book_scores[book_id as usize]
+    }
+
+    /// Ids of the libraries that hold a book
+    pub fn book_libraries(&self, book_id: u32) -> &[u32] {
+        self.book_libraries
+            .get(book_id as usize)
+            .map_or(&[], |ids| ids.as_slice())
+    }
+
+    /// Sorts book ids by decreasing score, then by id
+    pub fn sort_books(&self, books: &mut [u32]) {
+        books.sort_unstable_by_key(|book_id| {
+            (Reverse(self.book_scores[*book_id as usize]), *book_id)
+        });
+    }
+
+    pub fn new_book_set(&self) -> BookSet {
+        BookSet::new(self.num_books())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::example_task;
+
+    #[test]
+    fn book_set_tracks_books_across_words() {
+        let mut set = BookSet::new(130);
+        assert!(set.insert(0));
+        assert!(set.insert(64));
+        assert!(set.insert(129));
+        assert!(!set.insert(64));
+        assert!(set.contains(0) && set.contains(64) && set.contains(129));
+        assert!(!set.contains(1) && !set.contains(63) && !set.contains(128));
+        set.remove(64);
+        assert!(!set.contains(64));
+        assert!(set.contains(129));
+        set.clear();
+        assert!((0..130).all(|book_id| !set.contains(book_id)));
+    }
+
+    #[test]
+    fn indexes_example_books() {
+        let task = example_task();
+        let compact = task.compact();
+        assert_eq!(compact.num_books(), 6);
+        assert_eq!(compact.book_libraries(0), &[0, 1]);
+        assert_eq!(compact.book_libraries(5), &[1]);
+        assert_eq!(compact.book_libraries(6), &[] as &[u32]);
+        assert_eq!(task.book_library_counts(), vec![2, 1, 2, 2, 1, 1]);
+        let mut books = vec![0, 1, 2, 3, 4, 5];
+        compact.sort_books(&mut books);
+        assert_eq!(books, vec![3, 4, 5, 2, 1, 0]);
+    }
+}
@@ -0,0 +1,214 @@
+use super::assignment::assign_books;
+use super::planner::ScanningPlan;
+use super::{Library, ScanningTask};
+use std::cmp::Reverse;
+
+/// Outcome of an exact search: when the search was not cut short by the node
+/// limit, the upper bound equals the score and the plan is proven optimal.
+pub struct Certificate {
+    pub score: u64,
+    pub upper_bound: u64,
+    pub nodes: usize,
+}
+
+impl Certificate {
+    pub fn is_optimal(&self) -> bool {
+        self.score >= self.upper_bound
+    }
+}
+
+/// Branch-and-bound over sequences of signed-up libraries. Every sequence is
+/// scored with the optimal book assignment, so only library orders need to be
+/// enumerated. Only practical for small instances.
+pub struct ExactSolver<'a> {
+    task: &'a ScanningTask,
+    libraries: &'a [Library],
+    node_limit: usize,
+}
+
+struct SearchState<'a> {
+    best_order: Vec<&'a Library>,
+    best_score: u64,
+    open_bound: u64,
+    nodes: usize,
+}
+
+impl<'a> ExactSolver<'a> {
+    pub fn new(task: &'a ScanningTask, node_limit: usize) -> Self {
+        Self {
+            task,
+            libraries: &task.libraries,
+            node_limit,
+        }
+    }
+
+    pub fn solve(&self) -> (ScanningPlan<'a>, Certificate) {
+        let mut state = SearchState {
+            best_order: Vec::new(),
+            best_score: 0,
+            open_bound: 0,
+            nodes: 0,
+        };
+        let mut order = Vec::new();
+        self.search(&mut state, &mut order, self.task.days, 0);
+
+        let mut plan = ScanningPlan::new(self.task);
+        let assigned = assign_books(self.task, &state.best_order);
+        for (library, books) in state.best_order.iter().zip(assigned) {
+            plan.add_library(library, books);
+        }
+        let certificate = Certificate {
+            score: state.best_score,
+            upper_bound: state
+                .best_score
+                .max(state.open_bound.min(self.task.total_book_score())),
+            nodes: state.nodes,
+        };
+        (plan, certificate)
+    }
+
+    fn search(
+        &self,
+        state: &mut SearchState<'a>,
+        order: &mut Vec<&'a Library>,
+        days_left: u64,
+        score: u64,
+    ) {
+        state.nodes += 1;
+        if score > state.best_score {
+            state.best_score = score;
+            state.best_order = order.clone();
+        }
+
+        // Evaluate every library that can still be signed up, skipping those
+        // that would add nothing: dropping them never lowers the score
+        let mut children = Vec::new();
+        for library in self.libraries.iter() {
+            if library.signup_days >= days_left || order.contains(&library) {
+                continue;
+            }
+            order.push(library);
+            let child_score = sequence_score(self.task, order);
+            order.pop();
+            if child_score <= score {
+                continue;
+            }
+            let child_days = days_left - library.signup_days;
+            let bound =
+                child_score + self.extra_bound(order, library, child_days);
+            children.push((bound, child_score, library));
+        }
+        children.sort_unstable_by_key(|(bound, _, _)| Reverse(*bound));
+
+        for (bound, child_score, library) in children {
+            if bound <= state.best_score {
+                break;
+            }
+            if state.nodes >= self.node_limit {
+                state.open_bound = state.open_bound.max(bound);
+                continue;
+            }
+            order.push(library);
+            self.search(
+                state,
+                order,
+                days_left - library.signup_days,
+                child_score,
+            );
+            order.pop();
+        }
+    }
+
+    /// Bounds the score that libraries appended after `order` and `last` can
+    /// add: they scan at most as many books as their combined capacity, and
+    /// only books they hold.
+    fn extra_bound(
+        &self,
+        order: &[&Library],
+        last: &Library,
+        days_left: u64,
+    ) -> u64 {
+        let compact = self.task.compact();
+        let mut max_scans = 0;
+        let mut books = compact.new_book_set();
+        let mut scores = Vec::new();
+        for library in self.libraries.iter() {
+            if library.signup_days >= days_left
+                || library.id == last.id
+                || order.contains(&library)
+            {
+                continue;
+            }
+            max_scans += (days_left - library.signup_days) * library.scan_rate;
+            for book_id in library.books.iter() {
+                if books.insert(*book_id) {
+                    scores.push(compact.book_score(*book_id));
+                }
+            }
+        }
+        scores.sort_unstable();
+        scores.iter().rev().take(max_scans as usize).sum()
+    }
+}
+
+fn sequence_score(task: &ScanningTask, order: &[&Library]) -> u64 {
+    let compact = task.compact();
+    assign_books(task, order)
+        .iter()
+        .flatten()
+        .map(|book_id| compact.book_score(*book_id))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, SHARED_BOOKS};
+    use crate::validator::validate;
+
+    #[test]
+    fn proves_example_optimal() {
+        let task = example_task();
+        let (plan, certificate) = ExactSolver::new(&task, 1000).solve();
+        assert!(certificate.is_optimal());
+        assert_eq!(certificate.score, 21);
+        assert_eq!(certificate.upper_bound, 21);
+        assert!(validate(&task, &plan.to_string()).is_empty());
+        assert_eq!(plan.score().map(|(score, _, _)| score), Ok(21));
+    }
+
+    #[test]
+    fn matches_every_library_order() {
+        let task: ScanningTask = SHARED_BOOKS.parse().unwrap();
+        let mut best = 0;
+        let permutations = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        for permutation in permutations.iter() {
+            let order = permutation
+                .iter()
+                .map(|id| &task.libraries[*id])
+                .collect::<Vec<_>>();
+            for len in 0..=order.len() {
+                best = best.max(sequence_score(&task, &order[..len]));
+            }
+        }
+        let (_, certificate) = ExactSolver::new(&task, 1000).solve();
+        assert!(certificate.is_optimal());
+        assert_eq!(certificate.score, best);
+    }
+
+    #[test]
+    fn reports_open_bound_at_node_limit() {
+        let task = example_task();
+        let (_, certificate) = ExactSolver::new(&task, 1).solve();
+        assert_eq!(certificate.nodes, 1);
+        assert!(!certificate.is_optimal());
+        assert!(certificate.upper_bound >= 21);
+    }
+}
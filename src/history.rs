@@ -0,0 +1,220 @@
+use std::fs::{read_to_string, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str =
+    "timestamp,instance,solver,config,seed,score,idle_slots,libraries,elapsed";
+
+/// One line of the run log. The config holds the exponents and any other
+/// solver parameters of the run.
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    pub timestamp: u64,
+    pub instance: String,
+    pub solver: String,
+    pub config: String,
+    pub seed: Option<u64>,
+    pub score: u64,
+    pub idle_slots: u64,
+    pub libraries: usize,
+    pub elapsed: Duration,
+}
+
+impl RunRecord {
+    /// Seconds since the Unix epoch, for the timestamp of a new record
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+
+    /// Timestamp as a UTC date and time
+    pub fn date(&self) -> String {
+        let days = (self.timestamp / 86_400) as i64;
+        let secs = self.timestamp % 86_400;
+        // Civil date from days since the epoch, in 400-year eras
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs / 3_600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+
+    fn to_csv(&self) -> String {
+        // Free-text fields must not break the columns
+        let clean = |field: &str| field.replace([',', '\n'], ";");
+        format!(
+            "{},{},{},{},{},{},{},{},{:0.3}",
+            self.timestamp,
+            clean(&self.instance),
+            clean(&self.solver),
+            clean(&self.config),
+            self.seed.map_or(String::new(), |seed| seed.to_string()),
+            self.score,
+            self.idle_slots,
+            self.libraries,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+impl FromStr for RunRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split(',').collect::<Vec<_>>();
+        if fields.len() != 9 {
+            return Err(format!("Expected 9 fields, found {}", fields.len()));
+        }
+        let int = |field: &str| {
+            field
+                .parse::<u64>()
+                .map_err(|err| format!("{}: {}", err, field))
+        };
+        let seed = if fields[4].is_empty() {
+            None
+        } else {
+            Some(int(fields[4])?)
+        };
+        let elapsed = fields[8]
+            .parse::<f64>()
+            .map_err(|err| format!("{}: {}", err, fields[8]))?;
+        Ok(Self {
+            timestamp: int(fields[0])?,
+            instance: fields[1].to_string(),
+            solver: fields[2].to_string(),
+            config: fields[3].to_string(),
+            seed,
+            score: int(fields[5])?,
+            idle_slots: int(fields[6])?,
+            libraries: int(fields[7])? as usize,
+            elapsed: Duration::from_secs_f64(elapsed),
+        })
+    }
+}
+
+/// Appends a record to the run log, creating it with a header line first if
+/// it does not exist yet.
+pub fn append_record(path: &Path, record: &RunRecord) -> io::Result<()> {
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut lines = String::new();
+    if is_new {
+        lines.push_str(HEADER);
+        lines.push('\n');
+    }
+    lines.push_str(&record.to_csv());
+    lines.push('\n');
+    // A single write keeps lines from parallel runs whole
+    file.write_all(lines.as_bytes())
+}
+
+/// Reads all records of the run log, in the order they were appended
+pub fn read_records(path: &Path) -> Result<Vec<RunRecord>, String> {
+    let contents = read_to_string(path).map_err(|err| err.to_string())?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| *line != HEADER && !line.is_empty())
+        .map(|(index, line)| {
+            line.parse()
+                .map_err(|err| format!("Line {}: {}", index + 1, err))
+        })
+        .collect()
+}
+
+/// Highest-scoring record of every instance, sorted by instance. Ties go to
+/// the earliest run.
+pub fn best_per_instance(records: &[RunRecord]) -> Vec<&RunRecord> {
+    let mut best: Vec<&RunRecord> = Vec::new();
+    for record in records.iter() {
+        match best
+            .iter_mut()
+            .find(|best| best.instance == record.instance)
+        {
+            Some(best) if best.score < record.score => *best = record,
+            Some(_) => (),
+            None => best.push(record),
+        }
+    }
+    best.sort_by(|r1, r2| r1.instance.cmp(&r2.instance));
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::remove_file;
+
+    fn record(instance: &str, score: u64, timestamp: u64) -> RunRecord {
+        RunRecord {
+            timestamp,
+            instance: instance.to_string(),
+            solver: "greedy".to_string(),
+            config: "signup 1, idle 0".to_string(),
+            seed: None,
+            score,
+            idle_slots: 3,
+            libraries: 2,
+            elapsed: Duration::from_millis(250),
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut original = record("a", 21, 1_600_000_000);
+        original.seed = Some(42);
+        let line = original.to_csv();
+        assert_eq!(
+            line,
+            "1600000000,a,greedy,signup 1; idle 0,42,21,3,2,0.250"
+        );
+        let parsed = line.parse::<RunRecord>().unwrap();
+        assert_eq!(parsed.to_csv(), line);
+        assert!("1,a,greedy".parse::<RunRecord>().is_err());
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(record("a", 0, 0).date(), "1970-01-01 00:00:00");
+        assert_eq!(record("a", 0, 1_709_210_096).date(), "2024-02-29 12:34:56");
+    }
+
+    #[test]
+    fn appends_and_reads_log() {
+        let path = std::env::temp_dir()
+            .join(format!("history-{}.csv", std::process::id()));
+        let _ = remove_file(&path);
+        let records =
+            [record("b", 5, 1), record("a", 21, 2), record("b", 7, 3)];
+        for record in records.iter() {
+            append_record(&path, record).unwrap();
+        }
+        let contents = read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().next(), Some(HEADER));
+        let read = read_records(&path).unwrap();
+        assert_eq!(read.len(), 3);
+        let best = best_per_instance(&read);
+        let best = best
+            .iter()
+            .map(|record| (record.instance.as_str(), record.score))
+            .collect::<Vec<_>>();
+        assert_eq!(best, vec![("a", 21), ("b", 7)]);
+        remove_file(&path).unwrap();
+    }
+}
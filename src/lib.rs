@@ -1,56 +1,61 @@
+pub mod annealing;
+pub mod archive;
+pub mod assignment;
+pub mod bound;
+pub mod compact;
+pub mod exact;
+pub mod history;
 pub mod planner;
+pub mod repair;
+pub mod search;
+pub mod validator;
 
+mod submission;
+
+use compact::CompactTask;
 use std::borrow::Borrow;
 use std::cmp::Ordering;
-use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
-#[derive(Debug)]
-struct Book {
+/// A book and its score, read from the compact view of a task
+#[derive(Clone, Copy, Debug, Eq)]
+pub struct BookRef {
     id: u32,
     score: u64,
 }
 
-impl Hash for Book {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.id.hash(state);
-    }
-}
-
-impl PartialEq for Book {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
-    }
-}
-
-impl Eq for Book {}
-
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct BookRef(Rc<Book>);
-
 impl BookRef {
-    fn new(id: u32, score: u64) -> Self {
-        Self(Rc::new(Book { id, score }))
-    }
     pub fn id(&self) -> u32 {
-        self.0.id
+        self.id
     }
     pub fn score(&self) -> u64 {
-        self.0.score
+        self.score
     }
 }
 
 impl Borrow<u32> for BookRef {
     fn borrow(&self) -> &u32 {
-        &self.0.id
+        &self.id
+    }
+}
+
+impl Hash for BookRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialEq for BookRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
     }
 }
 
 impl Ord for BookRef {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.score.cmp(&other.0.score)
+        self.score.cmp(&other.score)
     }
 }
 
@@ -65,22 +70,13 @@ pub struct Library {
     pub id: u32,
     pub signup_days: u64,
     pub scan_rate: u64,
-    pub books: HashSet<BookRef>,
+    /// Book ids sorted by decreasing score, then by id
+    pub books: Vec<u32>,
 }
 
-impl Library {
-    fn new(
-        id: u32,
-        signup_days: u64,
-        scan_rate: u64,
-        books: HashSet<BookRef>,
-    ) -> Self {
-        Self {
-            id,
-            signup_days,
-            scan_rate,
-            books,
-        }
+impl Borrow<u32> for Library {
+    fn borrow(&self) -> &u32 {
+        &self.id
     }
 }
 
@@ -98,22 +94,23 @@ impl PartialEq for Library {
 
 pub struct ScanningTask {
     pub days: u64,
-    pub books: HashSet<BookRef>,
-    pub libraries: HashSet<Library>,
+    /// Libraries indexed by id, so they are also sorted by id
+    pub libraries: Vec<Library>,
+    compact: CompactTask,
+    /// Computed on first use, as it can take a while
+    upper_bound: OnceLock<u64>,
 }
 
 impl ScanningTask {
     fn new(days: u64, num_libraries: usize, book_scores: Vec<u32>) -> Self {
-        let books = book_scores
-            .iter()
-            .enumerate()
-            .map(|(id, score)| BookRef::new(id as u32, *score as u64))
-            .collect();
-        let libraries = HashSet::with_capacity(num_libraries);
+        let compact = CompactTask::new(
+            book_scores.iter().map(|score| *score as u64).collect(),
+        );
         Self {
             days,
-            books,
-            libraries,
+            libraries: Vec::with_capacity(num_libraries),
+            compact,
+            upper_bound: OnceLock::new(),
         }
     }
 
@@ -121,43 +118,97 @@ impl ScanningTask {
         &mut self,
         signup_days: u64,
         scan_rate: u64,
-        book_ids: Vec<u32>,
+        mut books: Vec<u32>,
     ) -> Result<(), String> {
         let id = self.libraries.len() as u32;
-        let books = book_ids
+        if let Some(book_id) = books
             .iter()
-            .map(|book_id| {
-                self.books.get(book_id).cloned().ok_or_else(|| {
-                    format!("Invalid book id {} in library {}", book_id, id)
-                })
-            })
-            .collect::<Result<_, _>>()?;
-        self.libraries
-            .insert(Library::new(id, signup_days, scan_rate, books));
+            .find(|book_id| **book_id as usize >= self.compact.num_books())
+        {
+            return Err(format!(
+                "Invalid book id {} in library {}",
+                book_id, id
+            ));
+        }
+        self.compact.sort_books(&mut books);
+        books.dedup();
+        self.compact.add_library(id, &books);
+        self.libraries.push(Library {
+            id,
+            signup_days,
+            scan_rate,
+            books,
+        });
         Ok(())
     }
 
+    pub fn num_books(&self) -> usize {
+        self.compact.num_books()
+    }
+
+    /// Score of a book, if it exists
+    pub fn book_score(&self, id: u32) -> Option<u64> {
+        self.compact.book_scores.get(id as usize).cloned()
+    }
+
+    pub fn book(&self, id: u32) -> Option<BookRef> {
+        self.book_score(id).map(|score| BookRef { id, score })
+    }
+
+    /// Books indexed by id
+    pub fn books(&self) -> impl Iterator<Item = BookRef> + '_ {
+        (0..self.num_books() as u32).filter_map(move |id| self.book(id))
+    }
+
+    /// Books of a library, sorted by decreasing score, then by id
+    pub fn library_books<'s>(
+        &'s self,
+        library: &'s Library,
+    ) -> impl Iterator<Item = BookRef> + 's {
+        library.books.iter().filter_map(move |id| self.book(*id))
+    }
+
+    pub fn library(&self, id: u32) -> Option<&Library> {
+        self.libraries.get(id as usize)
+    }
+
+    /// Ids of the libraries that hold a book
+    pub fn book_libraries(&self, book_id: u32) -> &[u32] {
+        self.compact.book_libraries(book_id)
+    }
+
+    /// Number of libraries that hold every book, indexed by book id
+    pub fn book_library_counts(&self) -> Vec<u32> {
+        (0..self.compact.num_books() as u32)
+            .map(|book_id| self.book_libraries(book_id).len() as u32)
+            .collect()
+    }
+
+    /// Index-based view of the books, used by planners and scorers
+    pub fn compact(&self) -> &CompactTask {
+        &self.compact
+    }
+
     pub fn min_book_score(&self) -> u64 {
-        self.books
+        self.compact
+            .book_scores
             .iter()
-            .map(|book| book.score())
             .min()
+            .cloned()
             .unwrap_or_default()
     }
 
     pub fn max_book_score(&self) -> u64 {
-        self.books
+        self.compact
+            .book_scores
             .iter()
-            .map(|book| book.score())
             .max()
+            .cloned()
             .unwrap_or_default()
     }
 
     pub fn total_book_score(&self) -> u64 {
-        self.books
-            .iter()
-            .map(|book| book.score())
-            .sum()
+        self.compact.book_scores.iter().sum()
     }
 
     pub fn total_book_copies(&self) -> u64 {
@@ -206,3 +257,55 @@ impl FromStr for ScanningTask {
         Ok(task)
     }
 }
+
+/// Tasks and submissions shared by the tests of every module
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::ScanningTask;
+
+    /// Example of the problem statement: 6 books, 2 libraries and 7 days
+    pub(crate) const EXAMPLE: &str = include_str!("../input/a_example.txt");
+
+    /// Optimal submission for the example, which scores 21
+    pub(crate) const EXAMPLE_SUBMISSION: &str = include_str!("../output/a.txt");
+
+    /// Books of equal score, each held by a single library
+    pub(crate) const READ_ON: &str = include_str!("../input/b_read_on.txt");
+
+    /// Three libraries that can all sign up, where the second one only holds
+    /// the book of the first one. The best plan drops it and scores 9.
+    pub(crate) const SHARED_BOOKS: &str =
+        "4 3 4\n4 3 2 1\n1 1 1\n0\n1 1 1\n0\n3 1 1\n1 2 3\n";
+
+    /// Two libraries holding the best book, of which only one can sign up,
+    /// and a third one holding it that never can
+    pub(crate) const EXPIRED_HOLDER: &str =
+        "2 3 3\n10 8\n1 2 1\n0\n1 2 1\n1\n1 3 1\n0\n";
+
+    pub(crate) fn example_task() -> ScanningTask {
+        EXAMPLE.parse().unwrap()
+    }
+
+    pub(crate) fn read_on_task() -> ScanningTask {
+        READ_ON.parse().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::example_task;
+
+    #[test]
+    fn book_accessors_read_compact_task() {
+        let task = example_task();
+        assert_eq!(task.book(3).map(|book| book.score()), Some(6));
+        assert_eq!(task.book(6), None);
+        assert_eq!(task.books().map(|book| book.score()).sum::<u64>(), 21);
+        let library = task.library(1).unwrap();
+        let books = task
+            .library_books(library)
+            .map(|book| book.id())
+            .collect::<Vec<_>>();
+        assert_eq!(books, vec![3, 5, 2, 0]);
+    }
+}
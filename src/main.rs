@@ -1,15 +1,388 @@
-use clap::{crate_description, value_t, values_t, App, Arg, ArgGroup};
-use hashcode2020::planner::{PlanBuilder, ScanningPlan, SignupExponent};
+use clap::{
+    crate_description, value_t, values_t, App, AppSettings, Arg, ArgGroup,
+    ArgMatches, ErrorKind, SubCommand,
+};
+use hashcode2020::annealing::{Annealer, Cooling};
+use hashcode2020::archive::{
+    best_submission, best_submissions, instance_name, save_submission,
+    RunMetadata,
+};
+use hashcode2020::assignment::optimal_assignment;
+use hashcode2020::exact::ExactSolver;
+use hashcode2020::history::{
+    append_record, best_per_instance, read_records, RunRecord,
+};
+use hashcode2020::planner::beam::Beam;
+use hashcode2020::planner::cross_entropy::CrossEntropy;
+use hashcode2020::planner::grasp::{Grasp, Selection};
+use hashcode2020::planner::lookahead::Lookahead;
+use hashcode2020::planner::schedule::ExponentSchedule;
+use hashcode2020::planner::scoring::{
+    scorer_by_name, LibraryScorer, SCORER_NAMES,
+};
+use hashcode2020::planner::sweep::{
+    ParameterRange, ParameterSweep, SweepStrategy,
+};
+use hashcode2020::planner::{
+    Construction, ExponentSequence, PlanBuilder, ScanningPlan, SignupExponent,
+};
+use hashcode2020::repair::repair;
+use hashcode2020::search::{LocalSearch, MoveKind};
+use hashcode2020::validator::validate;
 use hashcode2020::ScanningTask;
 use num_format::{Locale, ToFormattedString};
-use std::fs::{read_to_string, write};
+use rand::random;
+use std::env;
+use std::fs::{read_dir, read_to_string, write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::thread;
+use std::time::{Duration, Instant};
 
 fn main() {
-    let (input_filename, output_filename, idle_exp, signup_exp) = get_args();
+    let start = Instant::now();
+    let args = get_args();
     println!(crate_description!());
 
-    let task = read_input(&input_filename);
+    match args.subcommand() {
+        ("solve", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            print_task_stats(&task);
+            let seed = get_seed(args);
+            let (plan, exponents) = solve_task(&task, args, seed);
+            print_plan_stats(&task, &plan);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &plan);
+                write_seed_file(filename, seed, exponents.as_ref());
+            }
+            record_run(
+                args,
+                "solve",
+                &plan,
+                Some(seed),
+                exponents.as_ref(),
+                start,
+            );
+        }
+        ("batch", Some(args)) => {
+            let inputs = list_inputs(args.value_of("directory").unwrap());
+            let archive = Path::new(args.value_of("archive").unwrap());
+            let seed = get_seed(args);
+            let solve_input = |path: &PathBuf| {
+                let start = Instant::now();
+                let task = read_input(&path.to_string_lossy());
+                println!("Instance: {}", path.display());
+                let (plan, exponents) = solve_task(&task, args, seed);
+                let best =
+                    best_submission(&task, archive, &instance_name(path))
+                        .map(|(score, _)| score);
+                record_instance(
+                    args,
+                    "batch",
+                    &path.to_string_lossy(),
+                    &plan,
+                    Some(seed),
+                    exponents.as_ref(),
+                    start.elapsed(),
+                );
+                let name = path.file_stem().unwrap_or_default();
+                (
+                    name.to_string_lossy().to_string(),
+                    plan.judge_score().0,
+                    best,
+                )
+            };
+            let results = if args.is_present("parallel") {
+                thread::scope(|scope| {
+                    let workers = inputs
+                        .iter()
+                        .map(|path| scope.spawn(move || solve_input(path)))
+                        .collect::<Vec<_>>();
+                    workers
+                        .into_iter()
+                        .map(|worker| worker.join().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            } else {
+                inputs.iter().map(solve_input).collect()
+            };
+            print_batch_results(&results);
+        }
+        ("sweep", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            print_task_stats(&task);
+            let ranges = values_t!(args.values_of("parameter"), ParameterRange)
+                .unwrap_or_else(|e| e.exit());
+            let strategy = value_t!(args.value_of("strategy"), SweepStrategy)
+                .unwrap_or_else(|e| e.exit());
+            let seed = get_seed(args);
+            let scorer = get_scorer(args);
+            let mut plan =
+                ParameterSweep::new(&task, ranges, strategy, scorer, seed)
+                    .run();
+            if args.is_present("optimal_assignment") {
+                plan = optimal_assignment(&plan);
+            }
+            print_plan_stats(&task, &plan);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &plan);
+                write_seed_file(filename, seed, None);
+            }
+            record_run(args, "sweep", &plan, Some(seed), None, start);
+        }
+        ("score", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            let plan =
+                read_submission(&task, args.value_of("submission").unwrap());
+            let (score, unscanned_count) = plan.judge_score();
+            println!(
+                "Judge score: {}\n\
+                 Listed books never scanned: {}",
+                score.to_formatted_string(&Locale::en),
+                unscanned_count.to_formatted_string(&Locale::en),
+            );
+            print_plan_stats(&task, &plan.judged());
+        }
+        ("repair", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            let input = read_file(args.value_of("submission").unwrap());
+            let plan = ScanningPlan::parse_lenient(&task, &input);
+            let repaired = repair(&plan);
+            println!(
+                "Libraries listed: {}\n\
+                 Books listed: {}",
+                plan.count_signedup_libraries()
+                    .to_formatted_string(&Locale::en),
+                plan.count_scanned_books().to_formatted_string(&Locale::en),
+            );
+            print_plan_stats(&task, &repaired);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &repaired);
+            }
+        }
+        ("improve", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            let plan =
+                read_submission(&task, args.value_of("submission").unwrap());
+            let seed = get_seed(args);
+            let search = get_local_search(args, seed)
+                .unwrap_or_else(|| LocalSearch::new(100_000, None, seed));
+            let mut improved = search.improve(&plan);
+            if args.is_present("optimal_assignment") {
+                improved = optimal_assignment(&improved);
+            }
+            print_plan_stats(&task, &improved);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &improved);
+                write_seed_file(filename, seed, None);
+            }
+            record_run(args, "improve", &improved, Some(seed), None, start);
+        }
+        ("anneal", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            print_task_stats(&task);
+            let seed = get_seed(args);
+            let annealer = get_annealer(args, seed);
+            let (plan, exponents) = match args.value_of("start") {
+                Some(filename) => (read_submission(&task, filename), None),
+                None => build_plan(&task, args, seed),
+            };
+            let mut improved = annealer.anneal(&plan);
+            if args.is_present("optimal_assignment") {
+                improved = optimal_assignment(&improved);
+            }
+            print_plan_stats(&task, &improved);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &improved);
+                write_seed_file(filename, seed, exponents.as_ref());
+            }
+            record_run(
+                args,
+                "anneal",
+                &improved,
+                Some(seed),
+                exponents.as_ref(),
+                start,
+            );
+        }
+        ("assign", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            let plan =
+                read_submission(&task, args.value_of("submission").unwrap());
+            let optimal = optimal_assignment(&plan);
+            print_plan_stats(&task, &optimal);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &optimal);
+            }
+            record_run(args, "assign", &optimal, None, None, start);
+        }
+        ("exact", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            let node_limit = value_t!(args.value_of("node_limit"), usize)
+                .unwrap_or_else(|e| e.exit());
+            print_task_stats(&task);
+            let (plan, certificate) =
+                ExactSolver::new(&task, node_limit).solve();
+            println!(
+                "Nodes explored: {}\n\
+                 Upper bound: {}\n\
+                 Proven optimal: {}",
+                certificate.nodes.to_formatted_string(&Locale::en),
+                certificate.upper_bound.to_formatted_string(&Locale::en),
+                if certificate.is_optimal() {
+                    "yes"
+                } else {
+                    "no"
+                },
+            );
+            print_plan_stats(&task, &plan);
+            if let Some(filename) = args.value_of("output") {
+                write_output(filename, &plan);
+            }
+            record_run(args, "exact", &plan, None, None, start);
+        }
+        ("validate", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            validate_submission(&task, args.value_of("submission").unwrap());
+        }
+        ("best", Some(args)) => {
+            let archive = args.value_of("archive").unwrap();
+            let inputs = args.value_of("inputs").unwrap();
+            let best = best_submissions(Path::new(archive), Path::new(inputs))
+                .unwrap_or_else(|err| {
+                    println!("Failed to read submissions: {}", err);
+                    exit(2);
+                });
+            println!("{:<8} {:>12}  File", "Instance", "Score");
+            for (instance, score, path) in best {
+                println!(
+                    "{:<8} {:>12}  {}",
+                    instance,
+                    score.to_formatted_string(&Locale::en),
+                    path.display()
+                );
+            }
+        }
+        ("history", Some(args)) => print_history(args),
+        ("stats", Some(args)) => {
+            let task = read_input(args.value_of("input").unwrap());
+            print_task_stats(&task);
+            println!(
+                "Upper bound: {}",
+                task.upper_bound().to_formatted_string(&Locale::en)
+            );
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn build_plan<'a>(
+    task: &'a ScanningTask,
+    args: &ArgMatches,
+    seed: u64,
+) -> (ScanningPlan<'a>, Option<ExponentSequence>) {
+    let idle_exp =
+        value_t!(args.value_of("idle_exp"), f32).unwrap_or_else(|e| e.exit());
+    println!("Idle exponent: {:0.4}", idle_exp);
+    let builder = PlanBuilder::new(
+        task,
+        idle_exp,
+        get_signup_exp(args),
+        get_scorer(args),
+        get_construction(args),
+        seed,
+    );
+    builder.build().unwrap_or_else(|err| {
+        println!("{}", err);
+        exit(3);
+    })
+}
+
+/// Builds a plan with the planner, then improves it with local search and
+/// the optimal book assignment when requested.
+fn solve_task<'a>(
+    task: &'a ScanningTask,
+    args: &ArgMatches,
+    seed: u64,
+) -> (ScanningPlan<'a>, Option<ExponentSequence>) {
+    let (mut plan, exponents) = build_plan(task, args, seed);
+    if let Some(search) = get_local_search(args, seed) {
+        plan = search.improve(&plan);
+    }
+    if args.is_present("optimal_assignment") {
+        plan = optimal_assignment(&plan);
+    }
+    (plan, exponents)
+}
+
+/// Score of an instance, or of all of them, against the best submission in
+/// the archive
+#[derive(Debug, PartialEq)]
+struct BatchRow<'a> {
+    name: &'a str,
+    score: u64,
+    best: Option<u64>,
+    difference: Option<i64>,
+}
+
+/// Rows of the batch results: every instance, then the total. The total has
+/// no best when an instance has none, as it would not compare like with like.
+fn batch_rows(results: &[(String, u64, Option<u64>)]) -> Vec<BatchRow<'_>> {
+    let row = |name, score, best: Option<u64>| BatchRow {
+        name,
+        score,
+        best,
+        difference: best.map(|best| score as i64 - best as i64),
+    };
+    let mut rows = results
+        .iter()
+        .map(|(name, score, best)| row(name.as_str(), *score, *best))
+        .collect::<Vec<_>>();
+    let total_score = results.iter().map(|(_, score, _)| score).sum();
+    let total_best = results.iter().map(|(_, _, best)| *best).sum();
+    rows.push(row("Total", total_score, total_best));
+    rows
+}
+
+/// Prints the score of every instance and its difference with the best
+/// submission in the archive
+fn print_batch_results(results: &[(String, u64, Option<u64>)]) {
+    let rows = batch_rows(results);
+    let width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:<width$} {:>12} {:>12} {:>12}",
+        "Instance",
+        "Score",
+        "Best",
+        "Difference",
+        width = width
+    );
+    for row in rows.iter() {
+        let difference = row.difference.map_or("-".to_string(), |difference| {
+            format!(
+                "{}{}",
+                if difference >= 0 { "+" } else { "" },
+                difference.to_formatted_string(&Locale::en)
+            )
+        });
+        println!(
+            "{:<width$} {:>12} {:>12} {:>12}",
+            row.name,
+            row.score.to_formatted_string(&Locale::en),
+            row.best.map_or("-".to_string(), |best| {
+                best.to_formatted_string(&Locale::en)
+            }),
+            difference,
+            width = width
+        );
+    }
+}
+
+fn print_task_stats(task: &ScanningTask) {
     let total_book_score = task.total_book_score();
     let book_copies = task.total_book_copies();
     println!(
@@ -18,26 +391,27 @@ fn main() {
          Book scores: {} - {} ({:0.2} average)\n\
          Max theoretical score: {}\n\
          Book copies: {} ({:0.2} average per book)\n\
-         Libraries: {}\n\
-         Idle exponent: {:0.4}",
+         Libraries: {}",
         task.days.to_formatted_string(&Locale::en),
-        task.books.len().to_formatted_string(&Locale::en),
+        task.num_books().to_formatted_string(&Locale::en),
         task.min_book_score().to_formatted_string(&Locale::en),
         task.max_book_score().to_formatted_string(&Locale::en),
-        (total_book_score as f32 / task.books.len() as f32),
+        (total_book_score as f32 / task.num_books() as f32),
         total_book_score.to_formatted_string(&Locale::en),
         book_copies.to_formatted_string(&Locale::en),
-        (book_copies as f32 / task.books.len() as f32),
+        (book_copies as f32 / task.num_books() as f32),
         task.libraries.len().to_formatted_string(&Locale::en),
-        idle_exp,
     );
-    let builder = PlanBuilder::new(&task, idle_exp, signup_exp);
-    let plan = builder.build();
+}
+
+fn print_plan_stats(task: &ScanningTask, plan: &ScanningPlan) {
+    let total_book_score = task.total_book_score();
     let (score, idle_library_count, idle_slot_count) =
         plan.score().unwrap_or_else(|err| {
             println!("Invalid output: {}", err);
             exit(4);
         });
+    let upper_bound = task.upper_bound().max(score);
     let scanned = plan.count_scanned_books();
     let signedup = plan.count_signedup_libraries();
     println!(
@@ -45,9 +419,11 @@ fn main() {
          Libraries signed-up: {} ({:.1}% of total)\n\
          Libraries partially idle: {} ({:.1}% of signed-up)\n\
          Total idle slots: {}\n\
-         Score: {} ({:.1}% of max theoretical)",
+         Score: {} ({:.1}% of max theoretical)\n\
+         Upper bound: {}\n\
+         Gap to upper bound: {} ({:.2}%)",
         scanned.to_formatted_string(&Locale::en),
-        (100_f32 * scanned as f32 / task.books.len() as f32),
+        (100_f32 * scanned as f32 / task.num_books() as f32),
         signedup.to_formatted_string(&Locale::en),
         (100_f32 * signedup as f32 / task.libraries.len() as f32),
         idle_library_count.to_formatted_string(&Locale::en),
@@ -55,109 +431,778 @@ fn main() {
         idle_slot_count.to_formatted_string(&Locale::en),
         score.to_formatted_string(&Locale::en),
         (100_f32 * score as f32 / total_book_score as f32),
+        upper_bound.to_formatted_string(&Locale::en),
+        (upper_bound - score).to_formatted_string(&Locale::en),
+        (100_f32 * (upper_bound - score) as f32 / upper_bound.max(1) as f32),
     );
-    if let Some(filename) = output_filename {
-        write_output(&filename, &plan);
-    }
 }
 
-fn read_input(filename: &str) -> ScanningTask {
-    let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+fn read_file(filename: &str) -> String {
+    read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    })
+}
+
+/// Input files of a directory, sorted by name
+fn list_inputs(dirname: &str) -> Vec<PathBuf> {
+    let entries = read_dir(dirname).unwrap_or_else(|err| {
+        println!("Failed to read directory '{}': {}", dirname, err);
         exit(2);
     });
+    let mut inputs = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect::<Vec<_>>();
+    inputs.sort_unstable();
+    inputs
+}
+
+fn read_input(filename: &str) -> ScanningTask {
+    let input = read_file(filename);
     input.parse().unwrap_or_else(|err: String| {
-        println!("Failed to parse input: {}", err.to_string());
+        println!("Failed to parse input: {}", err);
         exit(3);
     })
 }
 
+fn read_submission<'a>(
+    task: &'a ScanningTask,
+    filename: &str,
+) -> ScanningPlan<'a> {
+    ScanningPlan::parse(task, &read_file(filename)).unwrap_or_else(|err| {
+        println!("Failed to parse submission: {}", err);
+        exit(3);
+    })
+}
+
+fn validate_submission(task: &ScanningTask, filename: &str) {
+    let violations = validate(task, &read_file(filename));
+    for violation in violations.iter() {
+        println!("{}", violation);
+    }
+    let errors = violations
+        .iter()
+        .filter(|violation| violation.is_error())
+        .count();
+    if errors == 0 {
+        println!("Submission is valid");
+        return;
+    }
+    println!(
+        "Violations found: {}",
+        errors.to_formatted_string(&Locale::en)
+    );
+    exit(4);
+}
+
+/// Logs the run of a single-input command and saves its plan to the archive,
+/// each when requested
+fn record_run(
+    args: &ArgMatches,
+    solver: &str,
+    plan: &ScanningPlan,
+    seed: Option<u64>,
+    exponents: Option<&ExponentSequence>,
+    start: Instant,
+) {
+    let input = args.value_of("input").unwrap();
+    let runtime = start.elapsed();
+    record_instance(args, solver, input, plan, seed, exponents, runtime);
+}
+
+/// Appends a run to the run log and saves its plan to the archive, each when
+/// requested, the latter if it beats the best submission there for the same
+/// instance
+fn record_instance(
+    args: &ArgMatches,
+    solver: &str,
+    input: &str,
+    plan: &ScanningPlan,
+    seed: Option<u64>,
+    exponents: Option<&ExponentSequence>,
+    runtime: Duration,
+) {
+    let instance = instance_name(Path::new(input));
+    if let (Some(log), Ok((score, _, idle_slots))) =
+        (args.value_of("log"), plan.score())
+    {
+        let record = RunRecord {
+            timestamp: RunRecord::now(),
+            instance: instance.clone(),
+            solver: solver.to_string(),
+            config: describe_config(args),
+            seed,
+            score,
+            idle_slots,
+            libraries: plan.count_signedup_libraries(),
+            elapsed: runtime,
+        };
+        append_record(Path::new(log), &record).expect("Unable to write file");
+    }
+
+    if !args.is_present("save") {
+        return;
+    }
+    let archive = Path::new(args.value_of("archive").unwrap());
+    let metadata = RunMetadata {
+        solver: solver.to_string(),
+        parameters: env::args().skip(1).collect::<Vec<_>>().join(" "),
+        seed,
+        runtime,
+    };
+    let saved = save_submission(plan, archive, &instance, &metadata)
+        .expect("Unable to write file");
+    match saved {
+        Some(path) => {
+            if let Some(seed) = seed {
+                write_seed_file(&path.to_string_lossy(), seed, exponents);
+            }
+            println!("Saved to archive: {}", path.display());
+        }
+        None => println!(
+            "Not saved: archive has a submission for '{}' scoring at \
+             least as much",
+            instance
+        ),
+    }
+}
+
+/// Solver parameters of a run, including defaults, as `name=value` pairs
+fn describe_config(args: &ArgMatches) -> String {
+    const PARAMETERS: [&str; 20] = [
+        "scorer",
+        "idle_exp",
+        "signup_exp",
+        "signup_exp_range",
+        "variable_signup_exp",
+        "cross_entropy",
+        "signup_exp_schedule",
+        "replay",
+        "grasp",
+        "grasp_time_limit",
+        "beam",
+        "lookahead",
+        "parameter",
+        "strategy",
+        "start",
+        "temperature",
+        "cooling",
+        "moves",
+        "iterations",
+        "time_limit",
+    ];
+    let mut config = PARAMETERS
+        .iter()
+        .filter_map(|name| {
+            args.values_of(name).map(|values| {
+                let values = values.collect::<Vec<_>>().join("/");
+                format!("{}={}", name, values.replace(',', "/"))
+            })
+        })
+        .collect::<Vec<_>>();
+    if args.is_present("optimal_assignment") {
+        config.push("optimal_assignment".to_string());
+    }
+    config.join(" ")
+}
+
+fn print_history(args: &ArgMatches) {
+    let log = args.value_of("log").unwrap();
+    let records = read_records(Path::new(log)).unwrap_or_else(|err| {
+        println!("Failed to read run log '{}': {}", log, err);
+        exit(2);
+    });
+    let records = records
+        .into_iter()
+        .filter(|record| {
+            args.value_of("instance")
+                .is_none_or(|instance| record.instance == instance)
+                && args
+                    .value_of("solver")
+                    .is_none_or(|solver| record.solver == solver)
+                && args
+                    .value_of("config")
+                    .is_none_or(|config| record.config == config)
+        })
+        .collect::<Vec<_>>();
+    let shown = if args.is_present("trend") {
+        records.iter().collect()
+    } else {
+        best_per_instance(&records)
+    };
+
+    println!(
+        "{:<19} {:<8} {:<8} {:>12} {:>12} {:>10} {:>9}  {:<20}  Config",
+        "Date",
+        "Instance",
+        "Solver",
+        "Score",
+        "Idle slots",
+        "Libraries",
+        "Elapsed",
+        "Seed"
+    );
+    for record in shown {
+        println!(
+            "{:<19} {:<8} {:<8} {:>12} {:>12} {:>10} {:>8.2}s  {:<20}  {}",
+            record.date(),
+            record.instance,
+            record.solver,
+            record.score.to_formatted_string(&Locale::en),
+            record.idle_slots.to_formatted_string(&Locale::en),
+            record.libraries.to_formatted_string(&Locale::en),
+            record.elapsed.as_secs_f64(),
+            record.seed.map_or("-".to_string(), |seed| seed.to_string()),
+            record.config
+        );
+    }
+}
+
 fn write_output(filename: &str, plan: &ScanningPlan) {
     write(filename, plan.to_string()).expect("Unable to write file");
 }
 
-fn get_args() -> (String, Option<String>, f32, SignupExponent) {
-    let args = App::new(crate_description!())
-        .arg(
-            Arg::with_name("input")
-                .value_name("input file")
-                .help("Path to input file")
-                .required(true)
-                .index(1),
+/// Records the seed of a run, and the sign-up exponents it used when it can
+/// be replayed, in a `.seed` file next to the output file.
+fn write_seed_file(
+    filename: &str,
+    seed: u64,
+    exponents: Option<&ExponentSequence>,
+) {
+    let mut contents = format!("seed {}\n", seed);
+    if let Some(exponents) = exponents {
+        contents.push_str(&exponents.to_string());
+    }
+    let path = Path::new(filename).with_extension("seed");
+    write(path, contents).expect("Unable to write file");
+}
+
+fn read_seed_file(filename: &str) -> ExponentSequence {
+    parse_seed_file(&read_file(filename)).unwrap_or_else(|err| {
+        println!("Failed to parse seed file: {}", err);
+        exit(3);
+    })
+}
+
+/// Sign-up exponents recorded in a seed file, after the line of the seed
+fn parse_seed_file(contents: &str) -> Result<ExponentSequence, String> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with("seed"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .parse()
+}
+
+fn get_args() -> ArgMatches<'static> {
+    let input_arg = Arg::with_name("input")
+        .value_name("input file")
+        .help("Path to input file")
+        .required(true)
+        .index(1);
+    let submission_arg = Arg::with_name("submission")
+        .value_name("submission file")
+        .help("Path to submission file")
+        .required(true)
+        .index(2);
+    let output_arg = Arg::with_name("output")
+        .value_name("output file")
+        .help("Path to output file")
+        .short("o")
+        .long("output")
+        .takes_value(true);
+    let iterations_arg = Arg::with_name("iterations")
+        .value_name("count")
+        .help("Number of search iterations")
+        .short("n")
+        .long("iterations")
+        .takes_value(true);
+    let time_limit_arg = Arg::with_name("time_limit")
+        .value_name("seconds")
+        .help("Time limit for search")
+        .short("t")
+        .long("time-limit")
+        .takes_value(true);
+    let seed_arg = Arg::with_name("seed")
+        .value_name("seed")
+        .help("Random seed (runs limited by time are not reproducible)")
+        .long("seed")
+        .takes_value(true);
+    let archive_arg = Arg::with_name("archive")
+        .value_name("directory")
+        .help("Archive directory of best submissions")
+        .long("archive")
+        .takes_value(true)
+        .default_value("output");
+    let save_arg = Arg::with_name("save")
+        .help("Save the plan to the archive if it beats the best submission")
+        .long("save");
+    let log_arg = Arg::with_name("log")
+        .value_name("log file")
+        .help("Run log to append to")
+        .long("log")
+        .takes_value(true);
+    let assignment_arg = Arg::with_name("optimal_assignment")
+        .help("Reassign books optimally for the final library order")
+        .short("a")
+        .long("optimal-assignment");
+
+    App::new(crate_description!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(with_planner_args(
+            SubCommand::with_name("solve")
+                .about("Build a scanning plan with the greedy planner")
+                .arg(input_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone())
+                .arg(iterations_arg.clone())
+                .arg(time_limit_arg.clone())
+                .arg(seed_arg.clone())
+                .arg(assignment_arg.clone()),
+        ))
+        .subcommand(with_planner_args(
+            SubCommand::with_name("batch")
+                .about("Solve every input file of a directory")
+                .arg(
+                    Arg::with_name("directory")
+                        .value_name("input directory")
+                        .help("Path to directory of input files")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("parallel")
+                        .help("Solve all input files in parallel")
+                        .short("j")
+                        .long("parallel"),
+                )
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone())
+                .arg(iterations_arg.clone())
+                .arg(time_limit_arg.clone())
+                .arg(seed_arg.clone())
+                .arg(assignment_arg.clone()),
+        ))
+        .subcommand(
+            SubCommand::with_name("sweep")
+                .about("Search planner parameters for the best greedy plan")
+                .arg(input_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone())
+                .arg(seed_arg.clone())
+                .arg(assignment_arg.clone())
+                .arg(scorer_arg())
+                .arg(
+                    Arg::with_name("parameter")
+                        .value_name("name=min,max,step")
+                        .help(
+                            "Parameter to sweep: idle, signup or point<index> \
+                             for a control point of a sign-up exponent \
+                             schedule",
+                        )
+                        .short("p")
+                        .long("parameter")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .allow_hyphen_values(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("strategy")
+                        .value_name("strategy")
+                        .help(
+                            "Sweep strategy: grid, random:<count> or \
+                             adaptive:<count>",
+                        )
+                        .short("s")
+                        .long("strategy")
+                        .takes_value(true)
+                        .default_value("grid"),
+                ),
         )
-        .arg(
-            Arg::with_name("output")
-                .value_name("output file")
-                .help("Path to output file")
-                .short("o")
-                .long("output")
-                .takes_value(true),
+        .subcommand(
+            SubCommand::with_name("score")
+                .about("Score a submission as the judge would")
+                .arg(input_arg.clone())
+                .arg(submission_arg.clone()),
         )
-        .arg(
-            Arg::with_name("idle_exp")
-                .value_name("idle exponent")
-                .help("Idle exponent")
-                .short("i")
-                .long("idle-exp")
-                .takes_value(true)
-                .allow_hyphen_values(true)
-                .default_value("0"),
+        .subcommand(
+            SubCommand::with_name("repair")
+                .about("Turn a submission into the best valid one")
+                .arg(input_arg.clone())
+                .arg(submission_arg.clone())
+                .arg(output_arg.clone()),
         )
-        .arg(
-            Arg::with_name("signup_exp")
-                .value_name("signup exponent")
-                .help("Sign-up exponent")
-                .short("e")
-                .long("signup-exp")
-                .takes_value(true)
-                .allow_hyphen_values(true),
+        .subcommand(
+            SubCommand::with_name("improve")
+                .about("Improve a submission with local search")
+                .arg(input_arg.clone())
+                .arg(submission_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone())
+                .arg(iterations_arg.clone())
+                .arg(time_limit_arg.clone())
+                .arg(seed_arg.clone())
+                .arg(assignment_arg.clone()),
         )
-        .arg(
-            Arg::with_name("signup_exp_range")
-                .value_name("start,end,step")
-                .help("Best result from a range of sign-up exponents")
-                .short("r")
-                .long("signup-exp-range")
-                .takes_value(true)
-                .number_of_values(3)
-                .require_delimiter(true),
+        .subcommand(with_planner_args(
+            SubCommand::with_name("anneal")
+                .about("Improve a plan with simulated annealing")
+                .arg(input_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone())
+                .arg(iterations_arg)
+                .arg(time_limit_arg)
+                .arg(seed_arg)
+                .arg(assignment_arg)
+                .arg(
+                    Arg::with_name("start")
+                        .value_name("submission file")
+                        .help("Start from a submission instead of greedy plan")
+                        .short("s")
+                        .long("start")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("temperature")
+                        .value_name("temperature")
+                        .help("Starting temperature")
+                        .short("T")
+                        .long("temperature")
+                        .takes_value(true)
+                        .default_value("1000"),
+                )
+                .arg(
+                    Arg::with_name("cooling")
+                        .value_name("schedule")
+                        .help(
+                            "Cooling schedule: linear, geometric:<factor> or \
+                             exponential:<final temperature>",
+                        )
+                        .short("c")
+                        .long("cooling")
+                        .takes_value(true)
+                        .default_value("linear"),
+                )
+                .arg(
+                    Arg::with_name("moves")
+                        .value_name("move types")
+                        .help("Move types: swap, insert, remove, add")
+                        .short("m")
+                        .long("moves")
+                        .takes_value(true)
+                        .use_delimiter(true)
+                        .default_value("swap,insert,remove,add"),
+                ),
+        ))
+        .subcommand(
+            SubCommand::with_name("assign")
+                .about("Reassign books optimally for a submission's libraries")
+                .arg(input_arg.clone())
+                .arg(submission_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg.clone())
+                .arg(log_arg.clone()),
         )
-        .arg(
-            Arg::with_name("variable_signup_exp")
-                .value_name("count,min,max")
-                .help(
-                    "Best result from multiple runs using variable random \
-                     sign-up exponents at every step",
+        .subcommand(
+            SubCommand::with_name("exact")
+                .about("Solve a small input optimally with branch-and-bound")
+                .arg(input_arg.clone())
+                .arg(output_arg.clone())
+                .arg(archive_arg.clone())
+                .arg(save_arg)
+                .arg(log_arg.clone())
+                .arg(
+                    Arg::with_name("node_limit")
+                        .value_name("count")
+                        .help("Maximum number of search nodes")
+                        .short("l")
+                        .long("node-limit")
+                        .takes_value(true)
+                        .default_value("1000000"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Check a submission against all judge rules")
+                .arg(input_arg.clone())
+                .arg(submission_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("best")
+                .about("List the best submission for every instance")
+                .arg(archive_arg)
+                .arg(
+                    Arg::with_name("inputs")
+                        .value_name("directory")
+                        .help("Directory of input files to judge against")
+                        .long("inputs")
+                        .takes_value(true)
+                        .default_value("input"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Query the run log")
+                .arg(log_arg.help("Run log to read").required(true))
+                .arg(
+                    Arg::with_name("instance")
+                        .value_name("instance")
+                        .help("Only runs on an instance, e.g. e")
+                        .long("instance")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("solver")
+                        .value_name("solver")
+                        .help("Only runs of a solver command, e.g. solve")
+                        .long("solver")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .value_name("config")
+                        .help("Only runs with exactly this config")
+                        .long("config")
+                        .takes_value(true),
                 )
-                .short("v")
-                .long("variable-signup-exp")
-                .takes_value(true)
-                .number_of_values(3)
-                .require_delimiter(true),
+                .arg(
+                    Arg::with_name("trend")
+                        .help(
+                            "Show every matching run in order instead of the \
+                             best per instance",
+                        )
+                        .long("trend"),
+                ),
         )
-        .group(
-            ArgGroup::with_name("mode")
-                .args(&[
-                    "signup_exp",
-                    "signup_exp_range",
-                    "variable_signup_exp",
-                ])
-                .required(false),
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Show statistics about an input file")
+                .arg(input_arg),
         )
-        .get_matches();
+        .get_matches()
+}
 
-    let input_file = args.value_of("input").unwrap().to_string();
-    let output_file = args.value_of("output").map(str::to_string);
-    let idle_exp =
-        value_t!(args.value_of("idle_exp"), f32).unwrap_or_else(|e| e.exit());
-    let signup_exp = if args.is_present("signup_exp_range") {
+fn scorer_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("scorer")
+        .value_name("name")
+        .help("Library scoring heuristic of the greedy planner")
+        .long("scorer")
+        .takes_value(true)
+        .possible_values(&SCORER_NAMES)
+        .default_value("sum")
+}
+
+fn with_planner_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(scorer_arg())
+    .arg(
+        Arg::with_name("idle_exp")
+            .value_name("idle exponent")
+            .help("Idle exponent")
+            .short("i")
+            .long("idle-exp")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .default_value("0"),
+    )
+    .arg(
+        Arg::with_name("signup_exp")
+            .value_name("signup exponent")
+            .help("Sign-up exponent")
+            .short("e")
+            .long("signup-exp")
+            .takes_value(true)
+            .allow_hyphen_values(true),
+    )
+    .arg(
+        Arg::with_name("signup_exp_range")
+            .value_name("start,end,step")
+            .help("Best result from a range of sign-up exponents")
+            .short("r")
+            .long("signup-exp-range")
+            .takes_value(true)
+            .number_of_values(3)
+            .require_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("variable_signup_exp")
+            .value_name("count,min,max")
+            .help(
+                "Best result from multiple runs using variable random \
+                 sign-up exponents at every step",
+            )
+            .short("v")
+            .long("variable-signup-exp")
+            .takes_value(true)
+            .number_of_values(3)
+            .require_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("cross_entropy")
+            .value_name("generations,population,min,max,phases")
+            .help(
+                "Best result from cross-entropy search over the sign-up \
+                 exponent distribution of every phase of the days",
+            )
+            .short("x")
+            .long("cross-entropy")
+            .takes_value(true)
+            .number_of_values(5)
+            .require_delimiter(true),
+    )
+    .arg(
+        Arg::with_name("signup_exp_schedule")
+            .value_name("fraction:exponent,...")
+            .help(
+                "Sign-up exponent interpolated between control points over \
+                 the fraction of days elapsed",
+            )
+            .long("signup-exp-schedule")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("replay")
+            .value_name("seed file")
+            .help("Replay the sign-up exponents recorded in a seed file")
+            .long("replay")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::with_name("grasp")
+            .value_name("selection,restarts,iterations")
+            .help(
+                "Best result from restarts picking libraries at random among \
+                 the top:<k> or alpha:<alpha> best, each improved by local \
+                 search iterations",
+            )
+            .long("grasp")
+            .takes_value(true)
+            .number_of_values(3)
+            .require_delimiter(true)
+            .conflicts_with_all(&[
+                "signup_exp_range",
+                "variable_signup_exp",
+                "cross_entropy",
+                "replay",
+            ]),
+    )
+    .arg(
+        Arg::with_name("beam")
+            .value_name("width,branching")
+            .help(
+                "Beam search keeping the best partial plans at every step, \
+                 each expanded with its best candidate libraries",
+            )
+            .long("beam")
+            .takes_value(true)
+            .number_of_values(2)
+            .require_delimiter(true)
+            .conflicts_with_all(&[
+                "signup_exp_range",
+                "variable_signup_exp",
+                "cross_entropy",
+                "replay",
+                "grasp",
+            ]),
+    )
+    .arg(
+        Arg::with_name("lookahead")
+            .value_name("steps,candidates")
+            .help(
+                "Greedy picks looking ahead: every candidate library is \
+                 scored with the best follow-ups after signing it up",
+            )
+            .long("lookahead")
+            .takes_value(true)
+            .number_of_values(2)
+            .require_delimiter(true)
+            .conflicts_with_all(&[
+                "signup_exp_range",
+                "variable_signup_exp",
+                "cross_entropy",
+                "replay",
+                "grasp",
+                "beam",
+            ]),
+    )
+    .arg(
+        Arg::with_name("grasp_time_limit")
+            .value_name("seconds")
+            .help("Time limit for GRASP restarts")
+            .long("grasp-time-limit")
+            .takes_value(true)
+            .requires("grasp"),
+    )
+    .group(
+        ArgGroup::with_name("mode")
+            .args(&[
+                "signup_exp",
+                "signup_exp_range",
+                "variable_signup_exp",
+                "cross_entropy",
+                "signup_exp_schedule",
+                "replay",
+            ])
+            .required(false),
+    )
+}
+
+fn get_scorer(args: &ArgMatches) -> &'static dyn LibraryScorer {
+    let name = args.value_of("scorer").unwrap();
+    println!("Scorer: {}", name);
+    scorer_by_name(name).unwrap_or_else(|err| {
+        println!("{}", err);
+        exit(3);
+    })
+}
+
+fn get_signup_exp(args: &ArgMatches) -> SignupExponent {
+    if args.is_present("signup_exp_range") {
         let values = values_t!(args.values_of("signup_exp_range"), f32)
             .unwrap_or_else(|e| e.exit());
-        SignupExponent::Range(values[0], values[1], values[2])
+        let (start, end, step) = (values[0], values[1], values[2]);
+        if step <= 0.0 || end < start {
+            let err = format!(
+                "Invalid exponent range {} {} {}: the step must be positive \
+                 and the end at least the start",
+                start, end, step
+            );
+            clap::Error::with_description(&err, ErrorKind::InvalidValue).exit()
+        }
+        SignupExponent::Range(start, end, step)
     } else if args.is_present("variable_signup_exp") {
         let values = values_t!(args.values_of("variable_signup_exp"), f32)
             .unwrap_or_else(|e| e.exit());
         SignupExponent::Variable(values[0] as usize, values[1], values[2])
+    } else if args.is_present("cross_entropy") {
+        let values = values_t!(args.values_of("cross_entropy"), f32)
+            .unwrap_or_else(|e| e.exit());
+        SignupExponent::CrossEntropy(CrossEntropy {
+            generations: values[0] as usize,
+            population: values[1] as usize,
+            min_exp: values[2],
+            max_exp: values[3],
+            phases: values[4] as usize,
+        })
+    } else if args.is_present("signup_exp_schedule") {
+        let schedule =
+            value_t!(args.value_of("signup_exp_schedule"), ExponentSchedule)
+                .unwrap_or_else(|e| e.exit());
+        SignupExponent::Schedule(schedule)
+    } else if let Some(filename) = args.value_of("replay") {
+        SignupExponent::Replay(read_seed_file(filename))
     } else {
         let exp = if args.is_present("signup_exp") {
             value_t!(args.value_of("signup_exp"), f32)
@@ -166,7 +1211,193 @@ fn get_args() -> (String, Option<String>, f32, SignupExponent) {
             1.0
         };
         SignupExponent::Fixed(exp)
+    }
+}
+
+fn get_construction(args: &ArgMatches) -> Construction {
+    if let Some(values) = args.values_of("grasp") {
+        let values = values.collect::<Vec<_>>();
+        let invalid = |err: String| -> ! {
+            clap::Error::with_description(&err, ErrorKind::InvalidValue).exit()
+        };
+        let selection = values[0]
+            .parse::<Selection>()
+            .unwrap_or_else(|err| invalid(err));
+        let restarts = values[1]
+            .parse::<usize>()
+            .unwrap_or_else(|err| invalid(format!("{}: {}", err, values[1])));
+        let search_iterations = values[2]
+            .parse::<usize>()
+            .unwrap_or_else(|err| invalid(format!("{}: {}", err, values[2])));
+        let time_limit = if args.is_present("grasp_time_limit") {
+            let seconds = value_t!(args.value_of("grasp_time_limit"), f32)
+                .unwrap_or_else(|e| e.exit());
+            Some(Duration::from_secs_f32(seconds))
+        } else {
+            None
+        };
+        Construction::Grasp(Grasp {
+            selection,
+            restarts,
+            time_limit,
+            search_iterations,
+        })
+    } else if args.is_present("beam") {
+        let values = values_t!(args.values_of("beam"), usize)
+            .unwrap_or_else(|e| e.exit());
+        Construction::Beam(Beam {
+            width: values[0].max(1),
+            branching: values[1].max(1),
+        })
+    } else if args.is_present("lookahead") {
+        let values = values_t!(args.values_of("lookahead"), usize)
+            .unwrap_or_else(|e| e.exit());
+        Construction::Lookahead(Lookahead {
+            steps: values[0],
+            candidates: values[1].max(1),
+        })
+    } else {
+        Construction::Greedy
+    }
+}
+
+fn get_budget(args: &ArgMatches) -> Option<(usize, Option<Duration>)> {
+    if !args.is_present("iterations") && !args.is_present("time_limit") {
+        return None;
+    }
+    let iterations = if args.is_present("iterations") {
+        value_t!(args.value_of("iterations"), usize)
+            .unwrap_or_else(|e| e.exit())
+    } else {
+        usize::MAX
     };
+    let time_limit = if args.is_present("time_limit") {
+        let seconds = value_t!(args.value_of("time_limit"), f32)
+            .unwrap_or_else(|e| e.exit());
+        Some(Duration::from_secs_f32(seconds))
+    } else {
+        None
+    };
+    Some((iterations, time_limit))
+}
 
-    (input_file, output_file, idle_exp, signup_exp)
+fn get_seed(args: &ArgMatches) -> u64 {
+    let seed = if args.is_present("seed") {
+        value_t!(args.value_of("seed"), u64).unwrap_or_else(|e| e.exit())
+    } else {
+        random()
+    };
+    println!("Seed: {}", seed);
+    seed
+}
+
+fn get_local_search(args: &ArgMatches, seed: u64) -> Option<LocalSearch> {
+    get_budget(args).map(|(iterations, time_limit)| {
+        LocalSearch::new(iterations, time_limit, seed)
+    })
+}
+
+fn get_annealer(args: &ArgMatches, seed: u64) -> Annealer {
+    let (iterations, time_limit) =
+        get_budget(args).unwrap_or((1_000_000, None));
+    let start_temp = value_t!(args.value_of("temperature"), f64)
+        .unwrap_or_else(|e| e.exit());
+    let cooling = value_t!(args.value_of("cooling"), Cooling)
+        .unwrap_or_else(|e| e.exit());
+    let moves = values_t!(args.values_of("moves"), MoveKind)
+        .unwrap_or_else(|e| e.exit());
+    println!("Starting temperature: {:0.4}", start_temp);
+    Annealer::new(start_temp, cooling, moves, iterations, time_limit, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashcode2020::planner::scoring::SumScorer;
+
+    #[test]
+    fn seed_file_replays_every_signup_exponent() {
+        // A steep exponent signs up the short library first, a flat one the
+        // valuable library
+        let task: ScanningTask =
+            "2 2 10\n2 10\n1 1 1\n0\n1 3 1\n1\n".parse().unwrap();
+        let build = |signup_exp| {
+            PlanBuilder::new(
+                &task,
+                0_f32,
+                signup_exp,
+                &SumScorer,
+                Construction::Greedy,
+                7,
+            )
+            .build()
+            .unwrap()
+        };
+        let filename = env::temp_dir()
+            .join(format!("hashcode2020-replay-{}.txt", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        let signup_exps = vec![
+            SignupExponent::Fixed(2_f32),
+            SignupExponent::Range(1.5_f32, 2.5_f32, 0.5_f32),
+            SignupExponent::Variable(4, 1.5_f32, 2.5_f32),
+            SignupExponent::CrossEntropy(CrossEntropy {
+                generations: 2,
+                population: 4,
+                min_exp: 1.5_f32,
+                max_exp: 2.5_f32,
+                phases: 2,
+            }),
+            SignupExponent::Schedule("0:2,1:1.5".parse().unwrap()),
+            SignupExponent::Replay("10 2\n".parse().unwrap()),
+        ];
+        for signup_exp in signup_exps {
+            let (plan, exponents) = build(signup_exp);
+            assert_eq!(plan.to_string(), "2\n0 1\n0\n1 1\n1\n");
+            write_seed_file(filename, 1, exponents.as_ref());
+            let seed_file = Path::new(filename).with_extension("seed");
+            let exponents =
+                parse_seed_file(&read_file(seed_file.to_str().unwrap()));
+            let (replayed, _) =
+                build(SignupExponent::Replay(exponents.unwrap()));
+            assert_eq!(replayed.to_string(), plan.to_string());
+        }
+    }
+
+    #[test]
+    fn batch_total_needs_every_best() {
+        let results = vec![
+            ("a".to_string(), 21, Some(20)),
+            ("b".to_string(), 5, Some(7)),
+        ];
+        let rows = batch_rows(&results);
+        assert_eq!(rows[1].difference, Some(-2));
+        assert_eq!(
+            rows[2],
+            BatchRow {
+                name: "Total",
+                score: 26,
+                best: Some(27),
+                difference: Some(-1),
+            }
+        );
+
+        let results =
+            vec![("a".to_string(), 21, Some(20)), ("b".to_string(), 5, None)];
+        let rows = batch_rows(&results);
+        assert_eq!(rows[1].difference, None);
+        assert_eq!(
+            rows[2],
+            BatchRow {
+                name: "Total",
+                score: 26,
+                best: None,
+                difference: None,
+            }
+        );
+    }
+
+    #[test]
+    fn seed_file_without_exponents_does_not_replay() {
+        assert!(parse_seed_file("seed 1\n").is_err());
+    }
 }
@@ -1,23 +1,154 @@
-use super::{BookRef, Library, ScanningTask};
+pub mod beam;
+pub mod cross_entropy;
+pub mod grasp;
+pub mod lookahead;
+pub mod schedule;
+pub mod scoring;
+pub mod sweep;
+
+use super::compact::BookSet;
+use super::submission;
+use super::{Library, ScanningTask};
+use beam::Beam;
+use cross_entropy::CrossEntropy;
+use grasp::Grasp;
+use lookahead::Lookahead;
 use num_format::{Locale, ToFormattedString};
 use rand::distributions::{Distribution, Uniform};
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use schedule::ExponentSchedule;
+use scoring::{LibraryScorer, LibraryState};
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BinaryHeap, HashSet};
 use std::fmt::{Display, Formatter};
-use std::iter::{repeat, FromIterator};
 use std::mem::swap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::thread;
+
+/// Position of a library that is no longer pending
+const NOT_PENDING: usize = usize::MAX;
 
 pub enum SignupExponent {
     Fixed(f32),
     Range(f32, f32, f32),
     Variable(usize, f32, f32),
+    CrossEntropy(CrossEntropy),
+    /// Exponent as a function of the fraction of days elapsed
+    Schedule(ExponentSchedule),
+    /// Exponents recorded by an earlier run
+    Replay(ExponentSequence),
+}
+
+/// Sign-up exponents a plan was built with, as much as a replay needs
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExponentSequence {
+    /// Exponent shared by all pending libraries at every step, by the days
+    /// left at that step
+    Steps(Vec<(u64, f32)>),
+    /// Exponents drawn for every pending library at every step from a
+    /// uniform distribution between a minimum and a maximum, by seed
+    Drawn(u64, f32, f32),
+}
+
+impl ExponentSequence {
+    /// Exponent in effect with `days_left` days left. Past the recorded
+    /// steps, the last exponent is repeated.
+    fn exponent(steps: &[(u64, f32)], days_left: u64) -> f32 {
+        let index = steps.partition_point(|(days, _)| *days > days_left);
+        steps
+            .get(index)
+            .or_else(|| steps.last())
+            .map_or(1_f32, |(_, exp)| *exp)
+    }
+}
+
+impl FromStr for ExponentSequence {
+    type Err = String;
+
+    /// Parses `drawn <seed> <min> <max>`, or `<days left> <exponent>` lines
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|tokens| !tokens.is_empty())
+            .collect::<Vec<_>>();
+        let invalid = |tokens: &[&str]| {
+            format!("Invalid exponent record: {}", tokens.join(" "))
+        };
+        let parse_exp = |token: &str| {
+            token
+                .parse::<f32>()
+                .map_err(|err| format!("{}: {}", err, token))
+        };
+        let parse_int = |token: &str| {
+            token
+                .parse::<u64>()
+                .map_err(|err| format!("{}: {}", err, token))
+        };
+
+        match lines.first() {
+            Some(tokens) if tokens[0] == "drawn" => {
+                if tokens.len() != 4 || lines.len() > 1 {
+                    return Err(invalid(tokens));
+                }
+                Ok(ExponentSequence::Drawn(
+                    parse_int(tokens[1])?,
+                    parse_exp(tokens[2])?,
+                    parse_exp(tokens[3])?,
+                ))
+            }
+            None => Err("Empty exponent record".to_string()),
+            _ => lines
+                .iter()
+                .map(|tokens| {
+                    if tokens.len() != 2 {
+                        return Err(invalid(tokens));
+                    }
+                    Ok((parse_int(tokens[0])?, parse_exp(tokens[1])?))
+                })
+                .collect::<Result<_, _>>()
+                .map(ExponentSequence::Steps),
+        }
+    }
+}
+
+impl Display for ExponentSequence {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ExponentSequence::Steps(steps) => {
+                for (days_left, exp) in steps.iter() {
+                    writeln!(f, "{} {}", days_left, exp)?;
+                }
+                Ok(())
+            }
+            ExponentSequence::Drawn(seed, min_exp, max_exp) => {
+                writeln!(f, "drawn {} {} {}", seed, min_exp, max_exp)
+            }
+        }
+    }
+}
+
+/// How the planner picks the library to sign up at every step
+pub enum Construction {
+    /// The library with the highest score
+    Greedy,
+    /// Random picks among the best libraries, over restarts
+    Grasp(Grasp),
+    /// The best of several partial plans expanded side by side
+    Beam(Beam),
+    /// The library with the highest score plus best follow-ups
+    Lookahead(Lookahead),
 }
 
 pub struct PlanBuilder<'a> {
     task: &'a ScanningTask,
     idle_exp: f32,
     signup_exp: SignupExponent,
+    scorer: &'a dyn LibraryScorer,
+    construction: Construction,
+    seed: u64,
 }
 
 impl<'a> PlanBuilder<'a> {
@@ -25,123 +156,539 @@ impl<'a> PlanBuilder<'a> {
         task: &'a ScanningTask,
         idle_exp: f32,
         signup_exp: SignupExponent,
+        scorer: &'a dyn LibraryScorer,
+        construction: Construction,
+        seed: u64,
     ) -> Self {
         Self {
             task,
             idle_exp,
             signup_exp,
+            scorer,
+            construction,
+            seed,
         }
     }
 
-    pub fn build(&self) -> ScanningPlan {
+    /// Builds the best plan for the construction and sign-up exponent mode,
+    /// along with the exponents it used when the plan can be replayed, as
+    /// greedy plans can. Fails if the construction does not support the
+    /// sign-up exponent mode.
+    pub fn build(
+        &self,
+    ) -> Result<(ScanningPlan<'a>, Option<ExponentSequence>), String> {
+        let built = match &self.construction {
+            Construction::Greedy => self.build_greedy(),
+            _ if !matches!(
+                self.signup_exp,
+                SignupExponent::Fixed(_) | SignupExponent::Schedule(_)
+            ) =>
+            {
+                return Err("GRASP, beam search and lookahead only support a \
+                            fixed sign-up exponent or a schedule"
+                    .to_string());
+            }
+            Construction::Grasp(grasp) => {
+                println!(
+                    "GRASP: {} selection, {} restarts",
+                    grasp.selection, grasp.restarts
+                );
+                (self.build_grasp(grasp), None)
+            }
+            Construction::Beam(beam) => {
+                println!(
+                    "Beam search: width {}, branching {}",
+                    beam.width, beam.branching
+                );
+                (self.build_beam(beam), None)
+            }
+            Construction::Lookahead(lookahead) => {
+                println!(
+                    "Lookahead: {} steps, {} candidates",
+                    lookahead.steps, lookahead.candidates
+                );
+                (self.build_lookahead(lookahead), None)
+            }
+        };
+        Ok(built)
+    }
+
+    fn build_greedy(&self) -> (ScanningPlan<'a>, Option<ExponentSequence>) {
         match &self.signup_exp {
             SignupExponent::Fixed(exp) => {
                 println!("Sign-up exponent: {:0.4}", *exp);
-                self.build_plan(&mut repeat(*exp))
+                (self.build_fixed_plan(*exp), Some(self.fixed_steps(*exp)))
             }
             SignupExponent::Range(start, end, step) => {
-                let mut best_plan = ScanningPlan::new(self.task);
-                let mut best_score = 0;
+                let mut exponents = Vec::new();
                 let mut exp = *start;
                 while exp <= *end {
-                    let plan = self.build_plan(&mut repeat(exp));
-                    if let Ok((score, _, _)) = plan.score() {
+                    exponents.push(exp);
+                    exp += *step;
+                }
+                let (scores, best_index, best) =
+                    build_best(self.task, exponents.len(), |index| {
+                        self.build_fixed_plan(exponents[index])
+                    });
+                for (exp, score) in exponents.iter().zip(scores) {
+                    if let Some(score) = score {
                         println!(
                             "Sign-up exponent {:0.4}, score {}",
                             exp,
                             score.to_formatted_string(&Locale::en)
                         );
-                        if score > best_score {
-                            best_plan = plan;
-                            best_score = score;
-                        }
                     }
-                    exp += *step;
                 }
-                best_plan
+                let steps =
+                    best_index.map(|index| self.fixed_steps(exponents[index]));
+                (best, steps)
             }
             SignupExponent::Variable(count, min_exp, max_exp) => {
                 println!(
                     "Variable sign-up exponent: {:0.4} - {:0.4}",
                     *min_exp, *max_exp
                 );
-                let mut best_plan = ScanningPlan::new(self.task);
-                let mut best_score = 0;
                 if min_exp > max_exp {
-                    return best_plan;
+                    return (ScanningPlan::new(self.task), None);
                 }
 
-                let mut exponents = Uniform::new_inclusive(min_exp, max_exp)
-                    .sample_iter(thread_rng());
-                for i in 1..=*count {
-                    let plan = self.build_plan(&mut exponents);
-                    if let Ok((score, _, _)) = plan.score() {
+                // Every iteration has its own seed so that it can be
+                // reproduced regardless of how runs are spread over threads
+                let (scores, best_index, best) =
+                    build_best(self.task, *count, |index| {
+                        let seed = self.iteration_seed(index);
+                        self.build_drawn_plan(seed, *min_exp, *max_exp)
+                    });
+                for (i, score) in scores.into_iter().enumerate() {
+                    if let Some(score) = score {
                         println!(
                             "Iteration {}, score {}",
-                            i,
+                            i + 1,
                             score.to_formatted_string(&Locale::en)
                         );
-                        if score > best_score {
-                            best_plan = plan;
-                            best_score = score;
-                        }
                     }
                 }
-                best_plan
+                let exponents = best_index.map(|index| {
+                    let seed = self.iteration_seed(index);
+                    println!("Best iteration: {} (seed {})", index + 1, seed);
+                    ExponentSequence::Drawn(seed, *min_exp, *max_exp)
+                });
+                (best, exponents)
+            }
+            SignupExponent::CrossEntropy(search) => {
+                println!(
+                    "Cross-entropy sign-up exponent: {:0.4} - {:0.4}, \
+                     {} phases",
+                    search.min_exp, search.max_exp, search.phases
+                );
+                if search.min_exp > search.max_exp {
+                    return (ScanningPlan::new(self.task), None);
+                }
+                self.build_cross_entropy(search)
+            }
+            SignupExponent::Schedule(schedule) => {
+                println!("Sign-up exponent schedule: {}", schedule);
+                let plan = self.build_schedule_plan(schedule);
+                let steps = self.record_steps(&plan, |days_left| {
+                    schedule.exponent(self.elapsed(days_left))
+                });
+                (plan, Some(steps))
+            }
+            SignupExponent::Replay(ExponentSequence::Steps(steps)) => {
+                println!(
+                    "Replaying sign-up exponents: {} steps",
+                    steps.len().to_formatted_string(&Locale::en)
+                );
+                let plan = self.build_plan(|days_left| {
+                    ExponentSequence::exponent(steps, days_left)
+                });
+                (plan, Some(ExponentSequence::Steps(steps.clone())))
+            }
+            SignupExponent::Replay(ExponentSequence::Drawn(
+                seed,
+                min_exp,
+                max_exp,
+            )) => {
+                println!(
+                    "Replaying variable sign-up exponent: {:0.4} - {:0.4} \
+                     (seed {})",
+                    min_exp, max_exp, seed
+                );
+                let plan = self.build_drawn_plan(*seed, *min_exp, *max_exp);
+                (
+                    plan,
+                    Some(ExponentSequence::Drawn(*seed, *min_exp, *max_exp)),
+                )
             }
         }
     }
 
-    fn build_plan<I>(&self, signup_exp: &mut I) -> ScanningPlan
+    fn iteration_seed(&self, index: usize) -> u64 {
+        self.seed.wrapping_add(index as u64)
+    }
+
+    /// Fraction of the days elapsed with `days_left` days left
+    fn elapsed(&self, days_left: u64) -> f32 {
+        (self.task.days - days_left) as f32 / self.task.days as f32
+    }
+
+    /// Sign-up exponent at a step for the constructions that do not search
+    /// exponents, which `build` only allows with a fixed or scheduled one
+    fn exponent_at(&self, days_left: u64) -> f32 {
+        match &self.signup_exp {
+            SignupExponent::Fixed(exp) => *exp,
+            SignupExponent::Schedule(schedule) => {
+                schedule.exponent(self.elapsed(days_left))
+            }
+            _ => unreachable!("Sign-up exponent mode checked by build"),
+        }
+    }
+
+    /// Exponents of a plan built with a fixed exponent: a single step, which
+    /// is repeated to the end
+    fn fixed_steps(&self, signup_exp: f32) -> ExponentSequence {
+        ExponentSequence::Steps(vec![(self.task.days, signup_exp)])
+    }
+
+    /// Exponents of a plan built with an exponent that is a function of the
+    /// days left: the one in effect at the step of every sign-up
+    fn record_steps<F>(
+        &self,
+        plan: &ScanningPlan,
+        signup_exp: F,
+    ) -> ExponentSequence
     where
-        I: Iterator<Item = f32>,
+        F: Fn(u64) -> f32,
+    {
+        let mut days_left = self.task.days;
+        let mut steps = Vec::with_capacity(plan.count_signedup_libraries());
+        for (library, _) in plan.libraries() {
+            steps.push((days_left, signup_exp(days_left)));
+            days_left -= library.signup_days;
+        }
+        ExponentSequence::Steps(steps)
+    }
+
+    fn build_schedule_plan(
+        &self,
+        schedule: &ExponentSchedule,
+    ) -> ScanningPlan<'a> {
+        self.build_plan(|days_left| schedule.exponent(self.elapsed(days_left)))
+    }
+
+    /// Builds a plan drawing the sign-up exponent of every pending library
+    /// at every step from a uniform distribution seeded with `seed`
+    fn build_drawn_plan(
+        &self,
+        seed: u64,
+        min_exp: f32,
+        max_exp: f32,
+    ) -> ScanningPlan<'a> {
+        let distribution = Uniform::new_inclusive(min_exp, max_exp);
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.build_plan(|_| distribution.sample(&mut rng))
+    }
+
+    fn build_fixed_plan(&self, signup_exp: f32) -> ScanningPlan<'a> {
+        if self.idle_exp == 0_f32 && self.scorer.is_monotone() {
+            self.build_lazy_plan(signup_exp)
+        } else {
+            self.build_plan(|_| signup_exp)
+        }
+    }
+
+    /// Builds a plan drawing the sign-up exponent of every pending library
+    /// at every step from a function of the days left.
+    fn build_plan<F>(&self, signup_exp: F) -> ScanningPlan<'a>
+    where
+        F: FnMut(u64) -> f32,
+    {
+        self.build_plan_with(signup_exp, |pending_libraries, _, _| {
+            pending_libraries
+                .iter()
+                .enumerate()
+                .max_by(|(_, lib1), (_, lib2)| lib1.cmp(lib2))
+                .map(|(index, _)| index)
+        })
+    }
+
+    /// Same as `build_plan`, with a function picking the index of the next
+    /// library to sign up from the pending libraries, the number of them
+    /// holding every book and the days left, if any. Every pending library it
+    /// is given has a positive score, and it must leave the holders as it
+    /// found them.
+    fn build_plan_with<F, S>(
+        &self,
+        mut signup_exp: F,
+        mut select: S,
+    ) -> ScanningPlan<'a>
+    where
+        F: FnMut(u64) -> f32,
+        S: FnMut(&[PendingLibrary], &mut [u32], u64) -> Option<usize>,
     {
         let mut plan = ScanningPlan::new(self.task);
-        let mut pending_libraries = self
-            .task
-            .libraries
-            .iter()
-            .map(|library| PendingLibrary::new(library))
-            .collect::<Vec<_>>();
+        let mut pending_libraries = self.pending_libraries();
+        let mut positions = vec![NOT_PENDING; pending_libraries.len()];
+        let mut scanned = self.task.compact().new_book_set();
+        let mut holders = self.task.book_library_counts();
 
         let mut days_left = self.task.days;
         while days_left > 0 {
+            // Remove libraries that can no longer finish signing up, so that
+            // they no longer count as holders of their books
+            drop_libraries(&mut pending_libraries, &mut holders, |library| {
+                library.library.signup_days >= days_left
+            });
+
             // Update max scores of pending libraries
             for library in pending_libraries.iter_mut() {
-                library.update_score(
-                    days_left,
-                    self.idle_exp,
-                    signup_exp.next().unwrap(),
-                );
+                let exp = signup_exp(days_left);
+                self.update_score(library, days_left, exp, &holders);
             }
 
             // Remove libraries with max score zero
-            pending_libraries.retain(|library| library.score > 0_f32);
+            drop_libraries(&mut pending_libraries, &mut holders, |library| {
+                library.score <= 0_f32
+            });
+            positions.fill(NOT_PENDING);
+            for (index, library) in pending_libraries.iter().enumerate() {
+                positions[library.library.id as usize] = index;
+            }
 
-            if let Some(next_lib) = pending_libraries.iter_mut().max() {
+            if let Some(index) =
+                select(&pending_libraries, &mut holders, days_left)
+            {
                 // Sign up next library and select books for scanning
+                let next_lib = &mut pending_libraries[index];
+                release_books(&mut holders, &next_lib.books);
                 let scanned_books = next_lib.scan_books(days_left);
                 days_left -= next_lib.library.signup_days;
+                let signedup_library = next_lib.library;
+                self.remove_books(
+                    &mut pending_libraries,
+                    &positions,
+                    &scanned_books,
+                    &mut scanned,
+                );
+                plan.add_library(signedup_library, scanned_books);
+            } else {
+                break;
+            }
+        }
+        plan
+    }
+
+    /// Same as `build_plan` with a constant sign-up exponent, no idle
+    /// exponent and a monotone scorer. A library's score can then only go
+    /// down between steps, so stale scores in the priority queue are upper
+    /// bounds and only the libraries that reach the top are evaluated again.
+    fn build_lazy_plan(&self, signup_exp: f32) -> ScanningPlan<'a> {
+        let mut plan = ScanningPlan::new(self.task);
+        let mut pending_libraries = self.pending_libraries();
+        let positions = (0..pending_libraries.len()).collect::<Vec<_>>();
+        let mut scanned = self.task.compact().new_book_set();
+        let mut holders = self.task.book_library_counts();
+        let mut queue = BinaryHeap::new();
+        let mut days_left = self.task.days;
+        for (index, library) in pending_libraries.iter_mut().enumerate() {
+            self.update_score(library, days_left, signup_exp, &holders);
+            if library.score > 0_f32 {
+                queue.push(Candidate::new(library.score, index, 0));
+            }
+        }
 
-                // TODO: Fix Clippy warning
-                let signedup_library = next_lib.library.clone();
-                // Remove scanned books from remaining libraries
-                for library in pending_libraries.iter_mut() {
-                    library.remove_books(&scanned_books);
+        let mut step = 0;
+        while days_left > 0 {
+            let mut next_index = None;
+            while let Some(candidate) = queue.pop() {
+                if candidate.step == step {
+                    next_index = Some(candidate.index);
+                    break;
+                }
+                let library = &mut pending_libraries[candidate.index];
+                self.update_score(library, days_left, signup_exp, &holders);
+                if library.score > 0_f32 {
+                    queue.push(Candidate::new(
+                        library.score,
+                        candidate.index,
+                        step,
+                    ));
                 }
+            }
 
+            if let Some(index) = next_index {
+                // Sign up next library and select books for scanning
+                let next_lib = &mut pending_libraries[index];
+                release_books(&mut holders, &next_lib.books);
+                let scanned_books = next_lib.scan_books(days_left);
+                days_left -= next_lib.library.signup_days;
+                let signedup_library = next_lib.library;
+                self.remove_books(
+                    &mut pending_libraries,
+                    &positions,
+                    &scanned_books,
+                    &mut scanned,
+                );
                 plan.add_library(signedup_library, scanned_books);
+                step += 1;
             } else {
                 break;
             }
         }
         plan
     }
+
+    fn update_score(
+        &self,
+        library: &mut PendingLibrary,
+        days_left: u64,
+        signup_exp: f32,
+        holders: &[u32],
+    ) {
+        let state = LibraryState {
+            library: library.library,
+            books: &library.books,
+            days_left,
+            holders,
+            task: self.task.compact(),
+        };
+        library.score = self.scorer.score(&state, signup_exp, self.idle_exp);
+    }
+
+    /// Pending libraries indexed by library id
+    fn pending_libraries(&self) -> Vec<PendingLibrary<'a>> {
+        self.task
+            .libraries
+            .iter()
+            .map(PendingLibrary::new)
+            .collect()
+    }
+
+    /// Removes scanned books from the pending libraries that hold them,
+    /// found at their positions, indexed by library id
+    fn remove_books(
+        &self,
+        pending_libraries: &mut [PendingLibrary],
+        positions: &[usize],
+        scanned_books: &[u32],
+        scanned: &mut BookSet,
+    ) {
+        let mut affected: HashSet<u32> = HashSet::new();
+        for book_id in scanned_books.iter() {
+            scanned.insert(*book_id);
+            affected.extend(self.task.book_libraries(*book_id).iter().cloned());
+        }
+        for id in affected {
+            let position = positions[id as usize];
+            if position != NOT_PENDING {
+                pending_libraries[position].remove_books(scanned);
+            }
+        }
+    }
+}
+
+/// Takes the books of a library being signed up, or no longer pending, off
+/// the counts of pending libraries holding them
+fn release_books(holders: &mut [u32], books: &[u32]) {
+    for book_id in books.iter() {
+        holders[*book_id as usize] -= 1;
+    }
+}
+
+/// Removes the pending libraries matching a predicate and releases the books
+/// they still hold
+fn drop_libraries<P>(
+    pending_libraries: &mut Vec<PendingLibrary>,
+    holders: &mut [u32],
+    mut predicate: P,
+) where
+    P: FnMut(&PendingLibrary) -> bool,
+{
+    pending_libraries.retain(|library| {
+        let drop = predicate(library);
+        if drop {
+            release_books(holders, &library.books);
+        }
+        !drop
+    });
+}
+
+/// Builds `count` plans spread over all available CPU cores and returns
+/// the score of every valid plan, by index, along with the index of the
+/// best plan and the plan itself. Ties go to the lowest index, as if the
+/// plans were built in sequence.
+fn build_best<'a, F>(
+    task: &'a ScanningTask,
+    count: usize,
+    build: F,
+) -> (Vec<Option<u64>>, Option<usize>, ScanningPlan<'a>)
+where
+    F: Fn(usize) -> ScanningPlan<'a> + Sync,
+{
+    let threads = thread::available_parallelism()
+        .map_or(1, |threads| threads.get())
+        .min(count);
+    let next_index = AtomicUsize::new(0);
+    let results = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut scores = Vec::new();
+                    let mut best = None;
+                    loop {
+                        let index = next_index.fetch_add(1, Relaxed);
+                        if index >= count {
+                            break;
+                        }
+                        let built = build(index);
+                        let score =
+                            built.score().ok().map(|(score, _, _)| score);
+                        scores.push((index, score));
+                        if let Some(score) = score {
+                            if best.as_ref().is_none_or(
+                                |(best, _, _): &(u64, usize, _)| score > *best,
+                            ) {
+                                best = Some((score, index, built));
+                            }
+                        }
+                    }
+                    (scores, best)
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut scores = vec![None; count];
+    let mut best = None;
+    for (worker_scores, worker_best) in results {
+        for (index, score) in worker_scores {
+            scores[index] = score;
+        }
+        if let Some((score, index, built)) = worker_best {
+            if best.as_ref().is_none_or(
+                |(best_score, best_index, _): &(u64, usize, _)| {
+                    score > *best_score
+                        || (score == *best_score && index < *best_index)
+                },
+            ) {
+                best = Some((score, index, built));
+            }
+        }
+    }
+    match best {
+        Some((_, index, built)) => (scores, Some(index), built),
+        None => (scores, None, ScanningPlan::new(task)),
+    }
 }
 
+#[derive(Clone)]
 pub struct ScanningPlan<'a> {
     task: &'a ScanningTask,
-    queue: Vec<(&'a Library, HashSet<BookRef>)>,
+    queue: Vec<(&'a Library, Vec<u32>)>,
 }
 
 impl<'a> ScanningPlan<'a> {
@@ -152,15 +699,110 @@ impl<'a> ScanningPlan<'a> {
         }
     }
 
-    fn add_library(&mut self, library: &'a Library, books: HashSet<BookRef>) {
+    pub fn parse(task: &'a ScanningTask, input: &str) -> Result<Self, String> {
+        let mut plan = Self::new(task);
+        let mut lines = submission::lines(input);
+        let mut next_values = || -> Result<(usize, Vec<u32>), String> {
+            let line = lines.next().ok_or("Incomplete submission")?;
+            if let Some(invalid) = line.invalid.first() {
+                return Err(format!(
+                    "Line {}: {}: {}",
+                    line.number, invalid.error, invalid.token
+                ));
+            }
+            Ok((line.number, line.values))
+        };
+
+        let (line_num, values) = next_values()?;
+        if values.len() != 1 {
+            return Err(format!("Line {}: Invalid format", line_num));
+        }
+        let mut signedup_libraries = HashSet::new();
+        for _ in 0..values[0] {
+            let (line_num, values) = next_values()?;
+            if values.len() != 2 {
+                return Err(format!("Line {}: Invalid format", line_num));
+            }
+            let library = task.library(values[0]).ok_or_else(|| {
+                format!("Line {}: Invalid library id {}", line_num, values[0])
+            })?;
+            if !signedup_libraries.insert(library.id) {
+                return Err(format!(
+                    "Line {}: Library {} signed up more than once",
+                    line_num, library.id
+                ));
+            }
+            let (line_num, book_ids) = next_values()?;
+            if book_ids.len() != values[1] as usize {
+                return Err(format!(
+                    "Line {}: Expected {} books, found {}",
+                    line_num,
+                    values[1],
+                    book_ids.len()
+                ));
+            }
+            if let Some(book_id) = book_ids
+                .iter()
+                .find(|book_id| task.book_score(**book_id).is_none())
+            {
+                return Err(format!(
+                    "Line {}: Invalid book id {}",
+                    line_num, book_id
+                ));
+            }
+            plan.add_library(library, book_ids);
+        }
+
+        Ok(plan)
+    }
+
+    /// Parses as much of a submission as makes sense, for repairs. The
+    /// declared counts are ignored, every pair of lines after the first is a
+    /// library and its books, and entries with an invalid library id as well
+    /// as invalid tokens and book ids are skipped.
+    pub fn parse_lenient(task: &'a ScanningTask, input: &str) -> Self {
+        let mut plan = Self::new(task);
+        let mut lines = submission::lines(input).skip(1);
+        while let Some(line) = lines.next() {
+            let book_ids = lines.next().map_or_else(Vec::new, |line| {
+                line.values
+                    .into_iter()
+                    .filter(|book_id| task.book_score(*book_id).is_some())
+                    .collect()
+            });
+            let library = line.values.first().and_then(|id| task.library(*id));
+            if let Some(library) = library {
+                plan.add_library(library, book_ids);
+            }
+        }
+        plan
+    }
+
+    pub(crate) fn add_library(
+        &mut self,
+        library: &'a Library,
+        books: Vec<u32>,
+    ) {
         self.queue.push((library, books));
     }
 
+    pub fn task(&self) -> &'a ScanningTask {
+        self.task
+    }
+
+    pub fn libraries(&self) -> impl Iterator<Item = (&'a Library, &[u32])> {
+        self.queue
+            .iter()
+            .map(|(library, books)| (*library, books.as_slice()))
+    }
+
     pub fn score(&self) -> Result<(u64, u64, u64), String> {
         let mut idle_library_count = 0;
         let mut idle_slot_count = 0;
+        let compact = self.task.compact();
         let mut days_left = self.task.days;
-        let mut scanned_books: HashSet<BookRef> = HashSet::new();
+        let mut scanned = compact.new_book_set();
+        let mut score = 0;
         for (library, books) in self.queue.iter() {
             if library.signup_days > days_left {
                 return Err(format!(
@@ -185,13 +827,60 @@ impl<'a> ScanningPlan<'a> {
                     idle_slot_count += days_left - scan_days;
                 }
             }
-            scanned_books.extend(books.iter().cloned());
+            for book_id in books.iter() {
+                if scanned.insert(*book_id) {
+                    score += compact.book_score(*book_id);
+                }
+            }
         }
 
-        let score = scanned_books.iter().map(|book| book.score()).sum();
         Ok((score, idle_library_count, idle_slot_count))
     }
 
+    /// Scores the plan the way the official judge does: libraries sign up in
+    /// order, each one scans its books in the listed order and scanning stops
+    /// at the deadline. Returns the score and the number of listed books that
+    /// were never scanned.
+    pub fn judge_score(&self) -> (u64, u64) {
+        let compact = self.task.compact();
+        let mut unscanned_count = 0;
+        let mut days_left = self.task.days;
+        let mut scanned = compact.new_book_set();
+        let mut score = 0;
+        for (library, books) in self.queue.iter() {
+            days_left = days_left.saturating_sub(library.signup_days);
+            let max_scans = (days_left * library.scan_rate) as usize;
+            if books.len() > max_scans {
+                unscanned_count += (books.len() - max_scans) as u64;
+            }
+            for book_id in books.iter().take(max_scans) {
+                if scanned.insert(*book_id) {
+                    score += compact.book_score(*book_id);
+                }
+            }
+        }
+
+        (score, unscanned_count)
+    }
+
+    /// The plan as the judge scans it: libraries that cannot finish signing
+    /// up before the deadline are dropped, and every other library keeps
+    /// only the listed books it has time to scan.
+    pub fn judged(&self) -> ScanningPlan<'a> {
+        let mut judged = Self::new(self.task);
+        let mut days_left = self.task.days;
+        for (library, books) in self.queue.iter() {
+            if library.signup_days >= days_left {
+                break;
+            }
+            days_left -= library.signup_days;
+            let max_scans = (days_left * library.scan_rate) as usize;
+            let books = books.iter().take(max_scans).cloned().collect();
+            judged.add_library(library, books);
+        }
+        judged
+    }
+
     pub fn count_signedup_libraries(&self) -> usize {
         self.queue.len()
     }
@@ -203,21 +892,16 @@ impl<'a> ScanningPlan<'a> {
 
 struct PendingLibrary<'a> {
     library: &'a Library,
-    books: Vec<BookRef>,
+    books: Vec<u32>,
     score: f32,
-    idle_days: u64,
 }
 
 impl<'a> PendingLibrary<'a> {
     fn new(library: &'a Library) -> Self {
-        let mut books = library.books.iter().cloned().collect::<Vec<_>>();
-        books.sort_unstable();
-        books.reverse();
         Self {
             library,
-            books,
+            books: library.books.clone(),
             score: 0_f32,
-            idle_days: 0,
         }
     }
 
@@ -230,47 +914,49 @@ impl<'a> PendingLibrary<'a> {
         }
     }
 
-    fn update_score(&mut self, days_left: u64, idle_exp: f32, signup_exp: f32) {
-        let max_scans = self.max_scans(days_left);
-        if self.library.signup_days >= days_left {
-            self.score = 0_f32;
-            return;
-        }
-        self.score = self
-            .books
-            .iter()
-            .take(max_scans)
-            .map(|book| book.score())
-            .sum::<u64>() as f32;
-        if self.score == 0_f32 {
-            return;
-        }
-        self.score /= (self.library.signup_days as f32).powf(signup_exp);
-        if idle_exp == 0_f32 {
-            return;
-        }
-        self.idle_days = if max_scans > self.books.len() {
-            let scan_days = (self.books.len() as f32
-                / self.library.scan_rate as f32)
-                .ceil() as u64;
-            days_left - self.library.signup_days - scan_days
-        } else {
-            0
-        };
-        if self.idle_days > 0 {
-            self.score /= (self.idle_days as f32).powf(idle_exp);
-        }
-    }
-
-    fn scan_books(&mut self, days_left: u64) -> HashSet<BookRef> {
+    fn scan_books(&mut self, days_left: u64) -> Vec<u32> {
         self.books.truncate(self.max_scans(days_left));
         let mut selected = Vec::new();
         swap(&mut self.books, &mut selected);
-        HashSet::from_iter(selected)
+        selected
+    }
+
+    fn remove_books(&mut self, scanned: &BookSet) {
+        self.books.retain(|book_id| !scanned.contains(*book_id))
     }
+}
 
-    fn remove_books(&mut self, scanned_books: &HashSet<BookRef>) {
-        self.books.retain(|book| !scanned_books.contains(book))
+struct Candidate {
+    score: f32,
+    index: usize,
+    step: usize,
+}
+
+impl Candidate {
+    fn new(score: f32, index: usize, step: usize) -> Self {
+        Self { score, index, step }
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -284,15 +970,13 @@ impl Eq for PendingLibrary<'_> {}
 
 impl Ord for PendingLibrary<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.score
-            .partial_cmp(&other.score)
-            .unwrap_or(Ordering::Less)
+        self.score.total_cmp(&other.score)
     }
 }
 
 impl PartialOrd for PendingLibrary<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.score.partial_cmp(&other.score)
+        Some(self.cmp(other))
     }
 }
 
@@ -303,7 +987,7 @@ impl Display for ScanningPlan<'_> {
             writeln!(f, "{} {}", library.id, books.len())?;
             let book_list = books
                 .iter()
-                .map(|book| book.id().to_string())
+                .map(|book_id| book_id.to_string())
                 .collect::<Vec<_>>()
                 .join(" ");
             writeln!(f, "{}", book_list)?;
@@ -311,3 +995,202 @@ impl Display for ScanningPlan<'_> {
         Ok(())
     }
 }
+
+/// Plan built with a scorer and a construction, a fixed sign-up exponent of
+/// 1, no idle exponent and seed 0, for the tests of every construction
+#[cfg(test)]
+pub(crate) fn build_with<'a>(
+    task: &'a ScanningTask,
+    scorer: &'a dyn LibraryScorer,
+    construction: Construction,
+) -> ScanningPlan<'a> {
+    let builder = PlanBuilder::new(
+        task,
+        0_f32,
+        SignupExponent::Fixed(1_f32),
+        scorer,
+        construction,
+        0,
+    );
+    builder.build().unwrap().0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scoring::{PendingRarityScorer, SumScorer};
+    use super::*;
+    use crate::fixtures::{
+        example_task, read_on_task, EXAMPLE, EXAMPLE_SUBMISSION,
+        EXPIRED_HOLDER, READ_ON,
+    };
+
+    /// Same scores as `SumScorer`, without letting the planner evaluate them
+    /// lazily
+    struct EagerSumScorer;
+
+    impl LibraryScorer for EagerSumScorer {
+        fn score(
+            &self,
+            state: &LibraryState,
+            signup_exp: f32,
+            idle_exp: f32,
+        ) -> f32 {
+            SumScorer.score(state, signup_exp, idle_exp)
+        }
+    }
+
+    #[test]
+    fn parse_display_round_trip() {
+        let task = example_task();
+        let plan = ScanningPlan::parse(&task, EXAMPLE_SUBMISSION).unwrap();
+        assert_eq!(plan.to_string(), EXAMPLE_SUBMISSION);
+        let reparsed = ScanningPlan::parse(&task, &plan.to_string()).unwrap();
+        assert_eq!(reparsed.to_string(), plan.to_string());
+        assert_eq!(plan.count_signedup_libraries(), 2);
+        assert_eq!(plan.count_scanned_books(), 6);
+        assert_eq!(plan.score(), Ok((21, 2, 3)));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_submissions() {
+        let task = example_task();
+        let inputs = [
+            "",
+            "1\n2 1\n0\n",
+            "1\n0 2\n0\n",
+            "1\n0 1\n9\n",
+            "2\n0 1\n0\n0 1\n1\n",
+        ];
+        for input in inputs.iter() {
+            assert!(ScanningPlan::parse(&task, input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn judge_truncates_scans_at_deadline() {
+        let task = example_task();
+        let input = "2\n1 4\n5 3 2 0\n0 5\n4 3 2 1 0\n";
+        let plan = ScanningPlan::parse(&task, input).unwrap();
+        assert!(plan.score().is_err());
+        assert_eq!(plan.judge_score(), (21, 1));
+        let judged = plan.judged();
+        assert_eq!(judged.count_scanned_books(), 8);
+        assert_eq!(judged.score().map(|(score, _, _)| score), Ok(21));
+    }
+
+    #[test]
+    fn greedy_plan_is_optimal_on_example() {
+        let task = example_task();
+        let builder = PlanBuilder::new(
+            &task,
+            0_f32,
+            SignupExponent::Fixed(1_f32),
+            &SumScorer,
+            Construction::Greedy,
+            0,
+        );
+        let (plan, exponents) = builder.build().unwrap();
+        assert_eq!(plan.to_string(), EXAMPLE_SUBMISSION);
+        assert_eq!(exponents, Some(ExponentSequence::Steps(vec![(7, 1_f32)])));
+    }
+
+    #[test]
+    fn lazy_plan_matches_eager_plan() {
+        for input in [EXAMPLE, READ_ON] {
+            let task: ScanningTask = input.parse().unwrap();
+            for signup_exp in [0_f32, 1_f32, 2_f32] {
+                let build = |scorer| {
+                    PlanBuilder::new(
+                        &task,
+                        0_f32,
+                        SignupExponent::Fixed(signup_exp),
+                        scorer,
+                        Construction::Greedy,
+                        0,
+                    )
+                    .build_fixed_plan(signup_exp)
+                    .to_string()
+                };
+                assert_eq!(build(&SumScorer), build(&EagerSumScorer));
+            }
+        }
+    }
+
+    #[test]
+    fn build_best_breaks_ties_by_index() {
+        let task = example_task();
+        let inputs = [
+            EXAMPLE_SUBMISSION,
+            "1\n0 5\n4 3 2 1 0\n",
+            "1\n1 5\n5 3 2 0 0\n",
+        ];
+        let (scores, best_index, best) = build_best(&task, 9, |index| {
+            ScanningPlan::parse(&task, inputs[index % 3]).unwrap()
+        });
+        let expected = [Some(21), Some(17), None];
+        assert_eq!(scores, expected.repeat(3));
+        assert_eq!(best_index, Some(0));
+        assert_eq!(best.to_string(), EXAMPLE_SUBMISSION);
+    }
+
+    #[test]
+    fn exponent_sequence_round_trip() {
+        for input in ["7 1.5\n5 2\n", "drawn 42 0.5 1.5\n"] {
+            let sequence = input.parse::<ExponentSequence>().unwrap();
+            assert_eq!(sequence.to_string(), input);
+        }
+        let inputs = [
+            "",
+            "\n",
+            "drawn 1 2\n",
+            "7\n",
+            "7 1\ndrawn 1 2 3\n",
+            "x 1\n",
+        ];
+        for input in inputs.iter() {
+            assert!(input.parse::<ExponentSequence>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn exponent_sequence_steps() {
+        let steps = [(7, 1.5_f32), (5, 2_f32)];
+        assert_eq!(ExponentSequence::exponent(&steps, 7), 1.5_f32);
+        assert_eq!(ExponentSequence::exponent(&steps, 5), 2_f32);
+        assert_eq!(ExponentSequence::exponent(&steps, 1), 2_f32);
+        assert_eq!(ExponentSequence::exponent(&[], 7), 1_f32);
+    }
+
+    #[test]
+    fn replay_reproduces_variable_plan() {
+        let task = read_on_task();
+        let build = |signup_exp| {
+            PlanBuilder::new(
+                &task,
+                0_f32,
+                signup_exp,
+                &SumScorer,
+                Construction::Greedy,
+                7,
+            )
+            .build()
+            .unwrap()
+        };
+        let (plan, exponents) =
+            build(SignupExponent::Variable(4, 0.5_f32, 1.5_f32));
+        let exponents = exponents.unwrap();
+        assert!(matches!(exponents, ExponentSequence::Drawn(..)));
+        let (replayed, _) = build(SignupExponent::Replay(exponents));
+        assert_eq!(replayed.to_string(), plan.to_string());
+    }
+
+    #[test]
+    fn releases_libraries_that_cannot_sign_up() {
+        // Only one of the first two libraries can sign up, and the third one,
+        // which can never sign up, must not halve the best book's share
+        let task: ScanningTask = EXPIRED_HOLDER.parse().unwrap();
+        let plan =
+            build_with(&task, &PendingRarityScorer, Construction::Greedy);
+        assert_eq!(plan.to_string(), "1\n0 1\n0\n");
+    }
+}
@@ -0,0 +1,296 @@
+use super::scoring::LibraryState;
+use super::{release_books, PlanBuilder, ScanningPlan};
+use crate::bound::{fill_knapsack, sort_by_density};
+use crate::compact::BookSet;
+use crate::Library;
+use num_format::{Locale, ToFormattedString};
+use std::cmp::Reverse;
+use std::rc::Rc;
+
+/// Beam search over library orders: every step keeps the `width` best
+/// partial plans, each expanded with its `branching` best candidate
+/// libraries. Partial plans are ranked by their score plus an optimistic
+/// estimate of what the days left could add: the fractional knapsack of
+/// the other pending libraries, valued by what they could scan before the
+/// expansion. The plan made of the greedy picks is always kept, so that the
+/// search never does worse than the greedy planner.
+pub struct Beam {
+    pub width: usize,
+    pub branching: usize,
+}
+
+/// A library signed up in a partial plan, linked to the previous ones so
+/// that partial plans share their common prefix
+struct SignUp {
+    library: u32,
+    books: Vec<u32>,
+    previous: Option<Rc<SignUp>>,
+}
+
+#[derive(Clone)]
+struct PartialPlan {
+    last: Option<Rc<SignUp>>,
+    /// Libraries signed up, or that can no longer finish signing up
+    settled: Vec<bool>,
+    scanned: BookSet,
+    holders: Vec<u32>,
+    days_left: u64,
+    score: u64,
+}
+
+/// A pending library a partial plan may be expanded with
+struct Expansion {
+    /// Score of the expanded plan plus the estimate of the days left
+    bound: u64,
+    parent: usize,
+    library: u32,
+    books: Vec<u32>,
+    added: u64,
+}
+
+impl<'a> PlanBuilder<'a> {
+    pub(super) fn build_beam(&self, beam: &Beam) -> ScanningPlan<'a> {
+        let compact = self.task.compact();
+        let libraries = &self.task.libraries;
+
+        let mut root = PartialPlan {
+            last: None,
+            settled: vec![false; libraries.len()],
+            scanned: compact.new_book_set(),
+            holders: self.task.book_library_counts(),
+            days_left: self.task.days,
+            score: 0,
+        };
+        self.settle_expired(&mut root, libraries);
+        let mut states = vec![root];
+        let mut best: Option<PartialPlan> = None;
+        let mut books = Vec::new();
+        while !states.is_empty() {
+            let mut expansions = Vec::new();
+            for (parent, state) in states.iter().enumerate() {
+                let signup_exp = self.exponent_at(state.days_left);
+                // Sign-up days and score of the books every pending library
+                // could scan, which can only go down in later steps
+                let mut items = Vec::new();
+                let mut candidates = Vec::new();
+                for library in libraries.iter() {
+                    if state.settled[library.id as usize] {
+                        continue;
+                    }
+                    self.unscanned_books(library, &state.scanned, &mut books);
+                    let library_state = LibraryState {
+                        library,
+                        books: &books,
+                        days_left: state.days_left,
+                        holders: &state.holders,
+                        task: compact,
+                    };
+                    let score = self.scorer.score(
+                        &library_state,
+                        signup_exp,
+                        self.idle_exp,
+                    );
+                    if score <= 0_f32 {
+                        continue;
+                    }
+                    let scanned = library_state.scanned_books();
+                    let added = scanned
+                        .iter()
+                        .map(|book_id| compact.book_score(*book_id))
+                        .sum::<u64>();
+                    items.push((library.signup_days, added));
+                    candidates.push((score, library, scanned.len(), added));
+                }
+
+                // Highest scores first, ties to the highest id as in the
+                // greedy planner
+                candidates.sort_by(
+                    |(score1, lib1, _, _), (score2, lib2, _, _)| {
+                        score2.total_cmp(score1).then(lib2.id.cmp(&lib1.id))
+                    },
+                );
+                sort_by_density(&mut items);
+                if candidates.is_empty()
+                    && best.as_ref().is_none_or(|best| state.score > best.score)
+                {
+                    best = Some(state.clone());
+                }
+                for (_, library, scans, added) in
+                    candidates.into_iter().take(beam.branching)
+                {
+                    // Only the candidates kept get their books listed
+                    let mut books = Vec::with_capacity(scans);
+                    self.unscanned_books(library, &state.scanned, &mut books);
+                    books.truncate(scans);
+                    let score = state.score + added;
+                    let days_left = state.days_left - library.signup_days;
+                    // Pending libraries without the one signed up, or one
+                    // with the same sign-up days and score in its place
+                    let mut skipped = false;
+                    let others = items.iter().cloned().filter(|item| {
+                        let skip =
+                            !skipped && *item == (library.signup_days, added);
+                        skipped |= skip;
+                        !skip
+                    });
+                    // Like the knapsack bound of the task: any later library
+                    // must finish signing up before the deadline, so they all
+                    // fit in `days_left - 1` sign-up days, and each scans no
+                    // more than it could have before this expansion
+                    let estimate =
+                        fill_knapsack(others, days_left.saturating_sub(1));
+                    expansions.push(Expansion {
+                        bound: score + estimate,
+                        parent,
+                        library: library.id,
+                        books,
+                        added,
+                    });
+                }
+            }
+
+            // The first plan follows the greedy picks and is always kept
+            let greedy = match expansions.first() {
+                Some(expansion) if expansion.parent == 0 => {
+                    Some(expansions.remove(0))
+                }
+                _ => None,
+            };
+            // Stable sort, so that ties go to the earliest expansion
+            expansions.sort_by_key(|expansion| Reverse(expansion.bound));
+            expansions
+                .truncate(beam.width.saturating_sub(greedy.is_some() as usize));
+            expansions.splice(0..0, greedy);
+            states = expansions
+                .into_iter()
+                .map(|expansion| {
+                    let parent = &states[expansion.parent];
+                    let library = &libraries[expansion.library as usize];
+                    self.expand(parent, libraries, library, expansion)
+                })
+                .collect();
+        }
+
+        println!(
+            "Beam search score: {}",
+            best.as_ref()
+                .map_or(0, |best| best.score)
+                .to_formatted_string(&Locale::en)
+        );
+        let mut plan = ScanningPlan::new(self.task);
+        let mut signups = Vec::new();
+        let mut last = best.and_then(|best| best.last);
+        while let Some(signup) = last {
+            last = signup.previous.clone();
+            signups.push(signup);
+        }
+        for signup in signups.into_iter().rev() {
+            let library = &libraries[signup.library as usize];
+            plan.add_library(library, signup.books.clone());
+        }
+        plan
+    }
+
+    /// Books of a library not scanned yet, best first
+    fn unscanned_books(
+        &self,
+        library: &Library,
+        scanned: &BookSet,
+        books: &mut Vec<u32>,
+    ) {
+        books.clear();
+        books.extend(
+            library
+                .books
+                .iter()
+                .filter(|book_id| !scanned.contains(**book_id)),
+        );
+    }
+
+    /// Partial plan with one more library signed up
+    fn expand(
+        &self,
+        parent: &PartialPlan,
+        libraries: &[Library],
+        library: &Library,
+        expansion: Expansion,
+    ) -> PartialPlan {
+        let mut state = parent.clone();
+        let mut unscanned = Vec::new();
+        self.unscanned_books(library, &parent.scanned, &mut unscanned);
+        release_books(&mut state.holders, &unscanned);
+        for book_id in expansion.books.iter() {
+            state.scanned.insert(*book_id);
+        }
+        state.settled[library.id as usize] = true;
+        state.days_left -= library.signup_days;
+        self.settle_expired(&mut state, libraries);
+        state.score += expansion.added;
+        state.last = Some(Rc::new(SignUp {
+            library: library.id,
+            books: expansion.books,
+            previous: parent.last.clone(),
+        }));
+        state
+    }
+
+    /// Settles the libraries of a partial plan that can no longer finish
+    /// signing up, so that they no longer count as holders of their books
+    fn settle_expired(&self, state: &mut PartialPlan, libraries: &[Library]) {
+        let mut unscanned = Vec::new();
+        for library in libraries.iter() {
+            if !state.settled[library.id as usize]
+                && library.signup_days >= state.days_left
+            {
+                self.unscanned_books(library, &state.scanned, &mut unscanned);
+                release_books(&mut state.holders, &unscanned);
+                state.settled[library.id as usize] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{read_on_task, EXAMPLE, EXPIRED_HOLDER, READ_ON};
+    use crate::planner::build_with;
+    use crate::planner::scoring::{PendingRarityScorer, SumScorer};
+    use crate::planner::Construction;
+    use crate::ScanningTask;
+
+    fn beam(width: usize, branching: usize) -> Construction {
+        Construction::Beam(Beam { width, branching })
+    }
+
+    fn score(plan: &ScanningPlan) -> u64 {
+        plan.score().unwrap().0
+    }
+
+    #[test]
+    fn narrow_beam_reproduces_greedy_plan() {
+        for input in [EXAMPLE, READ_ON] {
+            let task: ScanningTask = input.parse().unwrap();
+            let greedy = build_with(&task, &SumScorer, Construction::Greedy);
+            let beam = build_with(&task, &SumScorer, beam(1, 1));
+            assert_eq!(beam.to_string(), greedy.to_string());
+        }
+    }
+
+    #[test]
+    fn wide_beam_never_does_worse() {
+        let task = read_on_task();
+        let greedy = build_with(&task, &SumScorer, Construction::Greedy);
+        let beam = build_with(&task, &SumScorer, beam(3, 2));
+        assert!(score(&beam) >= score(&greedy));
+    }
+
+    #[test]
+    fn settles_libraries_of_every_partial_plan() {
+        // Each partial plan releases the books of the libraries that can no
+        // longer sign up in it
+        let task: ScanningTask = EXPIRED_HOLDER.parse().unwrap();
+        let plan = build_with(&task, &PendingRarityScorer, beam(2, 2));
+        assert_eq!(plan.to_string(), "1\n0 1\n0\n");
+    }
+}
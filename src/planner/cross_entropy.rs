@@ -0,0 +1,209 @@
+use super::{build_best, ExponentSequence, PlanBuilder, ScanningPlan};
+use num_format::{Locale, ToFormattedString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::f32::consts::PI;
+use std::sync::Mutex;
+
+/// Share of every generation used to update the sampling distributions
+const ELITE_FRACTION: f32 = 0.2;
+/// Weight of the elite statistics in every update, the rest is kept
+const SMOOTHING: f32 = 0.7;
+
+/// Cross-entropy search for variable sign-up exponents. The days are split
+/// into phases of equal length, and every plan of a generation draws the
+/// sign-up exponent of each phase from a normal distribution for that phase,
+/// clamped to `min_exp - max_exp`. After every generation, the distributions
+/// move towards the exponents of the best plans, so the exponent is learned
+/// as a function of the days left.
+pub struct CrossEntropy {
+    pub generations: usize,
+    pub population: usize,
+    pub min_exp: f32,
+    pub max_exp: f32,
+    pub phases: usize,
+}
+
+/// Normal distribution of the sign-up exponent in a phase
+#[derive(Clone, Copy)]
+struct Phase {
+    mean: f32,
+    std_dev: f32,
+}
+
+impl<'a> PlanBuilder<'a> {
+    pub(super) fn build_cross_entropy(
+        &self,
+        search: &CrossEntropy,
+    ) -> (ScanningPlan<'a>, Option<ExponentSequence>) {
+        let phase_count = search.phases.max(1);
+        let min_std_dev = (search.max_exp - search.min_exp) / 100_f32;
+        let mut phases = vec![
+            Phase {
+                mean: (search.min_exp + search.max_exp) / 2_f32,
+                std_dev: (search.max_exp - search.min_exp) / 2_f32,
+            };
+            phase_count
+        ];
+        let elite_count =
+            ((search.population as f32 * ELITE_FRACTION) as usize).max(2);
+        // Score, plan and phase exponents of the best plan so far
+        let mut best: Option<(u64, ScanningPlan<'a>, Vec<f32>)> = None;
+
+        for generation in 0..search.generations {
+            // Exponent of every phase drawn by every plan
+            let drawn = Mutex::new(vec![Vec::new(); search.population]);
+            let (scores, best_index, plan) =
+                build_best(self.task, search.population, |index| {
+                    let seed = self
+                        .iteration_seed(generation * search.population + index);
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let exponents = phases
+                        .iter()
+                        .map(|phase| {
+                            (phase.mean + phase.std_dev * gaussian(&mut rng))
+                                .max(search.min_exp)
+                                .min(search.max_exp)
+                        })
+                        .collect::<Vec<_>>();
+                    let plan = self.build_plan(|days_left| {
+                        exponents[self.phase(days_left, phase_count)]
+                    });
+                    drawn.lock().unwrap()[index] = exponents;
+                    plan
+                });
+            let drawn = drawn.into_inner().unwrap();
+
+            let mut ranked = scores
+                .iter()
+                .enumerate()
+                .filter_map(|(index, score)| score.map(|score| (score, index)))
+                .collect::<Vec<_>>();
+            ranked.sort_unstable_by_key(|(score, index)| {
+                (Reverse(*score), *index)
+            });
+            for (index, phase) in phases.iter_mut().enumerate() {
+                let samples = ranked
+                    .iter()
+                    .take(elite_count)
+                    .map(|(_, plan_index)| drawn[*plan_index][index])
+                    .collect::<Vec<_>>();
+                if samples.is_empty() {
+                    continue;
+                }
+                let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+                let variance = samples
+                    .iter()
+                    .map(|exp| (exp - mean) * (exp - mean))
+                    .sum::<f32>()
+                    / samples.len() as f32;
+                phase.mean =
+                    SMOOTHING * mean + (1_f32 - SMOOTHING) * phase.mean;
+                phase.std_dev = (SMOOTHING * variance.sqrt()
+                    + (1_f32 - SMOOTHING) * phase.std_dev)
+                    .max(min_std_dev);
+            }
+
+            let generation_best = best_index.and_then(|index| scores[index]);
+            println!(
+                "Generation {}, best score {}, exponents {}",
+                generation + 1,
+                generation_best.map_or("-".to_string(), |score| {
+                    score.to_formatted_string(&Locale::en)
+                }),
+                phases
+                    .iter()
+                    .map(|phase| {
+                        format!("{:0.4} (sd {:0.4})", phase.mean, phase.std_dev)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            if let (Some(score), Some(index)) = (generation_best, best_index) {
+                if best.as_ref().is_none_or(|(best, _, _)| score > *best) {
+                    best = Some((score, plan, drawn[index].clone()));
+                }
+            }
+        }
+
+        match best {
+            Some((_, plan, exponents)) => {
+                let steps = self.record_steps(&plan, |days_left| {
+                    exponents[self.phase(days_left, phase_count)]
+                });
+                (plan, Some(steps))
+            }
+            None => (ScanningPlan::new(self.task), None),
+        }
+    }
+
+    /// Phase of the days, out of `phase_count` of equal length, a step with
+    /// `days_left` days left falls in
+    fn phase(&self, days_left: u64, phase_count: usize) -> usize {
+        let elapsed = self.elapsed(days_left);
+        ((elapsed * phase_count as f32) as usize).min(phase_count - 1)
+    }
+}
+
+/// Samples the standard normal distribution with the Box-Muller transform
+fn gaussian<R: Rng>(rng: &mut R) -> f32 {
+    let u1 = 1_f32 - rng.gen::<f32>();
+    let u2 = rng.gen::<f32>();
+    (-2_f32 * u1.ln()).sqrt() * (2_f32 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, read_on_task};
+    use crate::planner::scoring::SumScorer;
+    use crate::planner::{Construction, SignupExponent};
+    use crate::ScanningTask;
+
+    fn builder<'a>(
+        task: &'a ScanningTask,
+        signup_exp: SignupExponent,
+    ) -> PlanBuilder<'a> {
+        PlanBuilder::new(
+            task,
+            0_f32,
+            signup_exp,
+            &SumScorer,
+            Construction::Greedy,
+            3,
+        )
+    }
+
+    #[test]
+    fn splits_days_into_phases() {
+        let task = example_task();
+        let builder = builder(&task, SignupExponent::Fixed(1_f32));
+        let phases = (1..=7)
+            .rev()
+            .map(|days_left| builder.phase(days_left, 3))
+            .collect::<Vec<_>>();
+        assert_eq!(phases, vec![0, 0, 0, 1, 1, 2, 2]);
+        assert_eq!(builder.phase(0, 3), 2);
+    }
+
+    #[test]
+    fn replay_reproduces_best_plan() {
+        let task = read_on_task();
+        let search = CrossEntropy {
+            generations: 2,
+            population: 4,
+            min_exp: 0.5_f32,
+            max_exp: 1.5_f32,
+            phases: 2,
+        };
+        let (plan, exponents) = builder(&task, SignupExponent::Fixed(1_f32))
+            .build_cross_entropy(&search);
+        let exponents = exponents.unwrap();
+        assert!(matches!(exponents, ExponentSequence::Steps(_)));
+        let (replayed, _) = builder(&task, SignupExponent::Replay(exponents))
+            .build()
+            .unwrap();
+        assert_eq!(replayed.to_string(), plan.to_string());
+    }
+}
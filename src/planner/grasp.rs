@@ -0,0 +1,230 @@
+use super::{PendingLibrary, PlanBuilder, ScanningPlan};
+use crate::search::LocalSearch;
+use num_format::{Locale, ToFormattedString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Candidates a GRASP construction picks the next library from, at random
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    /// The libraries with the `k` highest scores
+    Top(usize),
+    /// The libraries scoring at least `best - alpha * (best - worst)`, so
+    /// that 0 is the greedy pick, but for ties, and 1 any library
+    Alpha(f32),
+}
+
+impl Selection {
+    /// Index of a random library among the candidates with a positive score
+    fn select<R: Rng>(
+        &self,
+        pending_libraries: &[PendingLibrary],
+        rng: &mut R,
+    ) -> Option<usize> {
+        let mut candidates = pending_libraries
+            .iter()
+            .enumerate()
+            .filter(|(_, library)| library.score > 0_f32)
+            .map(|(index, library)| (index, library.score))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        match *self {
+            Selection::Top(k) => {
+                // Only the best k are brought to the front, in no order
+                let count = k.clamp(1, candidates.len());
+                candidates.select_nth_unstable_by(
+                    count - 1,
+                    |(index1, score1), (index2, score2)| {
+                        score2.total_cmp(score1).then(index2.cmp(index1))
+                    },
+                );
+                candidates.truncate(count);
+            }
+            Selection::Alpha(alpha) => {
+                let scores = candidates.iter().map(|(_, score)| *score);
+                let best = scores.clone().max_by(f32::total_cmp).unwrap();
+                let worst = scores.min_by(f32::total_cmp).unwrap();
+                let threshold = best - alpha * (best - worst);
+                // The best library always qualifies, even if scores are NaN
+                candidates.retain(|(_, score)| {
+                    *score >= threshold || score.total_cmp(&best).is_eq()
+                });
+            }
+        }
+        Some(candidates[rng.gen_range(0, candidates.len())].0)
+    }
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    /// Parses `top:<k>` or `alpha:<alpha>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let kind = parts.next().unwrap_or_default();
+        let value = parts
+            .next()
+            .ok_or_else(|| format!("Invalid selection: {}", s))?;
+        match kind {
+            "top" => value
+                .parse()
+                .map(Selection::Top)
+                .map_err(|err| format!("{}: {}", err, value)),
+            "alpha" => value
+                .parse()
+                .map(Selection::Alpha)
+                .map_err(|err| format!("{}: {}", err, value)),
+            _ => Err(format!("Invalid selection: {}", s)),
+        }
+    }
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Selection::Top(k) => write!(f, "top:{}", k),
+            Selection::Alpha(alpha) => write!(f, "alpha:{}", alpha),
+        }
+    }
+}
+
+/// Greedy randomised adaptive search: every restart builds a plan picking
+/// libraries at random among the best candidates, then improves it with
+/// local search. The best plan over all restarts is kept.
+pub struct Grasp {
+    pub selection: Selection,
+    pub restarts: usize,
+    /// Stops restarting once elapsed, even if restarts are left
+    pub time_limit: Option<Duration>,
+    /// Local search iterations on every plan, none if 0
+    pub search_iterations: usize,
+}
+
+impl<'a> PlanBuilder<'a> {
+    pub(super) fn build_grasp(&self, grasp: &Grasp) -> ScanningPlan<'a> {
+        let start_time = Instant::now();
+        let mut best: Option<(u64, usize, ScanningPlan<'a>)> = None;
+        for restart in 0..grasp.restarts {
+            if let Some(limit) = grasp.time_limit {
+                if start_time.elapsed() >= limit {
+                    break;
+                }
+            }
+            // Every restart has its own seed so that it can be reproduced
+            let seed = self.iteration_seed(restart);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut plan = self.build_plan_with(
+                |days_left| self.exponent_at(days_left),
+                |pending_libraries, _, _| {
+                    grasp.selection.select(pending_libraries, &mut rng)
+                },
+            );
+            if grasp.search_iterations > 0 {
+                let search =
+                    LocalSearch::new(grasp.search_iterations, None, seed);
+                plan = search.improve(&plan);
+            }
+            if let Ok((score, _, _)) = plan.score() {
+                println!(
+                    "Restart {}, score {}",
+                    restart + 1,
+                    score.to_formatted_string(&Locale::en)
+                );
+                if best.as_ref().is_none_or(|(best, _, _)| score > *best) {
+                    best = Some((score, restart, plan));
+                }
+            }
+        }
+
+        match best {
+            Some((_, restart, plan)) => {
+                println!(
+                    "Best restart: {} (seed {})",
+                    restart + 1,
+                    self.iteration_seed(restart)
+                );
+                plan
+            }
+            None => ScanningPlan::new(self.task),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, EXAMPLE, READ_ON};
+    use crate::planner::build_with;
+    use crate::planner::scoring::SumScorer;
+    use crate::planner::{Construction, SignupExponent};
+    use crate::ScanningTask;
+
+    fn grasp(selection: Selection) -> Construction {
+        Construction::Grasp(Grasp {
+            selection,
+            restarts: 2,
+            time_limit: None,
+            search_iterations: 0,
+        })
+    }
+
+    #[test]
+    fn parses_selections() {
+        for input in ["top:3", "alpha:0.25"] {
+            let selection = input.parse::<Selection>().unwrap();
+            assert_eq!(selection.to_string(), input);
+        }
+        for input in ["top", "top:x", "alpha:", "best:1"] {
+            assert!(input.parse::<Selection>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn top_one_reproduces_greedy_plan() {
+        for input in [EXAMPLE, READ_ON] {
+            let task: ScanningTask = input.parse().unwrap();
+            let greedy = build_with(&task, &SumScorer, Construction::Greedy);
+            let top = build_with(&task, &SumScorer, grasp(Selection::Top(1)));
+            assert_eq!(top.to_string(), greedy.to_string());
+        }
+    }
+
+    #[test]
+    fn alpha_zero_picks_best_libraries() {
+        // Unlike the greedy planner, ties are broken at random, and the
+        // example has none
+        let task = example_task();
+        let greedy = build_with(&task, &SumScorer, Construction::Greedy);
+        let alpha =
+            build_with(&task, &SumScorer, grasp(Selection::Alpha(0_f32)));
+        assert_eq!(alpha.to_string(), greedy.to_string());
+    }
+
+    #[test]
+    fn random_selections_build_valid_plans() {
+        let task = example_task();
+        for selection in [Selection::Top(2), Selection::Alpha(1_f32)] {
+            let plan = build_with(&task, &SumScorer, grasp(selection));
+            assert!(plan.score().is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_searched_exponents() {
+        let task = example_task();
+        let builder = PlanBuilder::new(
+            &task,
+            0_f32,
+            SignupExponent::Variable(2, 0_f32, 1_f32),
+            &SumScorer,
+            grasp(Selection::Top(1)),
+            0,
+        );
+        assert!(builder.build().is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use super::scoring::LibraryState;
+use super::{release_books, PendingLibrary, PlanBuilder, ScanningPlan};
+use crate::compact::BookSet;
+
+/// Greedy planner looking ahead: each of the `candidates` best libraries is
+/// signed up in simulation, the pending libraries are scored again without
+/// its books, and the score of the best follow-up is added to its own, for
+/// `steps` follow-ups. The candidate with the highest combined score is
+/// signed up. With no steps, this is the greedy planner.
+///
+/// Scores are the scorer's priorities, ratios such as book score per sign-up
+/// day, so the combined score adds the ratios of the steps rather than the
+/// book scores they would add to the plan.
+pub struct Lookahead {
+    pub steps: usize,
+    pub candidates: usize,
+}
+
+/// Scratch state of the simulated sign-ups, undone after every candidate
+struct Simulation<'s> {
+    /// Books scanned by the simulated sign-ups
+    scanned: &'s mut BookSet,
+    /// Pending holders of every book, borrowed from the planner
+    holders: &'s mut [u32],
+    /// Indices of the libraries signed up in simulation
+    signed_up: Vec<usize>,
+}
+
+impl<'a> PlanBuilder<'a> {
+    pub(super) fn build_lookahead(
+        &self,
+        lookahead: &Lookahead,
+    ) -> ScanningPlan<'a> {
+        let mut scanned = self.task.compact().new_book_set();
+        self.build_plan_with(
+            |days_left| self.exponent_at(days_left),
+            |pending_libraries, holders, days_left| {
+                let scores = pending_libraries
+                    .iter()
+                    .map(|library| library.score)
+                    .collect::<Vec<_>>();
+                let mut simulation = Simulation {
+                    scanned: &mut scanned,
+                    holders,
+                    signed_up: Vec::new(),
+                };
+                let (index, _) = self.best_combined(
+                    pending_libraries,
+                    &scores,
+                    days_left,
+                    lookahead.steps + 1,
+                    lookahead.candidates.max(1),
+                    &mut simulation,
+                )?;
+                Some(index)
+            },
+        )
+    }
+
+    /// Candidate with the highest score plus best follow-up over `depth`
+    /// sign-ups, along with that combined score
+    fn best_combined(
+        &self,
+        pending_libraries: &[PendingLibrary],
+        scores: &[f32],
+        days_left: u64,
+        depth: usize,
+        candidates: usize,
+        simulation: &mut Simulation,
+    ) -> Option<(usize, f32)> {
+        let mut ranked = scores
+            .iter()
+            .enumerate()
+            .filter(|(_, score)| **score > 0_f32)
+            .map(|(index, score)| (index, *score))
+            .collect::<Vec<_>>();
+        // Highest scores first, ties to the highest index as in the greedy
+        // planner
+        ranked.sort_by(|(index1, score1), (index2, score2)| {
+            score2.total_cmp(score1).then(index2.cmp(index1))
+        });
+        ranked.truncate(if depth > 1 { candidates } else { 1 });
+
+        let mut best: Option<(usize, f32)> = None;
+        for (index, score) in ranked {
+            let combined = if depth > 1 {
+                score
+                    + self.follow_up(
+                        pending_libraries,
+                        index,
+                        days_left,
+                        depth - 1,
+                        candidates,
+                        simulation,
+                    )
+            } else {
+                score
+            };
+            if best.is_none_or(|(_, best)| combined > best) {
+                best = Some((index, combined));
+            }
+        }
+        best
+    }
+
+    /// Best combined score of `depth` sign-ups after a simulated sign-up
+    fn follow_up(
+        &self,
+        pending_libraries: &[PendingLibrary],
+        index: usize,
+        days_left: u64,
+        depth: usize,
+        candidates: usize,
+        simulation: &mut Simulation,
+    ) -> f32 {
+        // Sign up the library in simulation
+        let library = &pending_libraries[index];
+        let books = library
+            .books
+            .iter()
+            .filter(|book_id| !simulation.scanned.contains(**book_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let max_scans = library.max_scans(days_left).min(books.len());
+        for book_id in books[..max_scans].iter() {
+            simulation.scanned.insert(*book_id);
+        }
+        release_books(simulation.holders, &books);
+        simulation.signed_up.push(index);
+        let previous_days_left = days_left;
+        let days_left = days_left - library.library.signup_days;
+
+        // Release the libraries that can no longer finish signing up, as the
+        // planner would
+        let expired = pending_libraries
+            .iter()
+            .enumerate()
+            .filter(|(other, library)| {
+                let signup_days = library.library.signup_days;
+                signup_days >= days_left
+                    && signup_days < previous_days_left
+                    && !simulation.signed_up.contains(other)
+            })
+            .map(|(other, _)| other)
+            .collect::<Vec<_>>();
+        for other in expired.iter() {
+            release_books(simulation.holders, &pending_libraries[*other].books);
+        }
+
+        let signup_exp = self.exponent_at(days_left);
+        let mut scores = Vec::with_capacity(pending_libraries.len());
+        let mut books_left = Vec::new();
+        for (other, library) in pending_libraries.iter().enumerate() {
+            if simulation.signed_up.contains(&other) {
+                scores.push(0_f32);
+                continue;
+            }
+            books_left.clear();
+            books_left.extend(
+                library
+                    .books
+                    .iter()
+                    .filter(|book_id| !simulation.scanned.contains(**book_id)),
+            );
+            let state = LibraryState {
+                library: library.library,
+                books: &books_left,
+                days_left,
+                holders: simulation.holders,
+                task: self.task.compact(),
+            };
+            scores.push(self.scorer.score(&state, signup_exp, self.idle_exp));
+        }
+        let follow_up = self
+            .best_combined(
+                pending_libraries,
+                &scores,
+                days_left,
+                depth,
+                candidates,
+                simulation,
+            )
+            .map_or(0_f32, |(_, score)| score);
+
+        // Undo the simulated sign-up
+        for other in expired.iter() {
+            for book_id in pending_libraries[*other].books.iter() {
+                simulation.holders[*book_id as usize] += 1;
+            }
+        }
+        simulation.signed_up.pop();
+        for book_id in books.iter() {
+            simulation.holders[*book_id as usize] += 1;
+        }
+        for book_id in books[..max_scans].iter() {
+            simulation.scanned.remove(*book_id);
+        }
+        follow_up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{
+        example_task, read_on_task, EXAMPLE, EXPIRED_HOLDER, READ_ON,
+    };
+    use crate::planner::build_with;
+    use crate::planner::scoring::{
+        LibraryScorer, PendingRarityScorer, SumScorer,
+    };
+    use crate::planner::Construction;
+    use crate::ScanningTask;
+
+    fn lookahead(steps: usize, candidates: usize) -> Construction {
+        Construction::Lookahead(Lookahead { steps, candidates })
+    }
+
+    #[test]
+    fn no_steps_reproduce_greedy_plan() {
+        for input in [EXAMPLE, READ_ON] {
+            let task: ScanningTask = input.parse().unwrap();
+            for scorer in
+                [&SumScorer as &dyn LibraryScorer, &PendingRarityScorer]
+            {
+                let greedy = build_with(&task, scorer, Construction::Greedy);
+                let lookahead = build_with(&task, scorer, lookahead(0, 3));
+                assert_eq!(lookahead.to_string(), greedy.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn single_candidate_reproduces_greedy_plan() {
+        let task = read_on_task();
+        let greedy = build_with(&task, &SumScorer, Construction::Greedy);
+        let lookahead = build_with(&task, &SumScorer, lookahead(2, 1));
+        assert_eq!(lookahead.to_string(), greedy.to_string());
+    }
+
+    #[test]
+    fn finds_optimal_example_plan() {
+        let task = example_task();
+        let plan = build_with(&task, &PendingRarityScorer, lookahead(2, 2));
+        assert_eq!(plan.score().map(|(score, _, _)| score), Ok(21));
+    }
+
+    #[test]
+    fn simulation_releases_libraries_that_cannot_sign_up() {
+        // Simulated sign-ups release the books of the libraries they leave
+        // no time for, and restore them afterwards
+        let task: ScanningTask = EXPIRED_HOLDER.parse().unwrap();
+        let plan = build_with(&task, &PendingRarityScorer, lookahead(1, 2));
+        assert_eq!(plan.to_string(), "1\n0 1\n0\n");
+    }
+}
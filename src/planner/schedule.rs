@@ -0,0 +1,124 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Sign-up exponent as a piecewise-linear function of the fraction of days
+/// elapsed, given by control points. Before the first and after the last
+/// control point the exponent is constant.
+#[derive(Clone, Debug)]
+pub struct ExponentSchedule {
+    points: Vec<(f32, f32)>,
+}
+
+impl ExponentSchedule {
+    /// Schedule through `(elapsed fraction, exponent)` control points
+    pub fn new(mut points: Vec<(f32, f32)>) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("Schedule has no control points".to_string());
+        }
+        if let Some((fraction, _)) = points
+            .iter()
+            .find(|(fraction, _)| !(0_f32..=1_f32).contains(fraction))
+        {
+            return Err(format!(
+                "Control point at {} is outside 0 - 1",
+                fraction
+            ));
+        }
+        points.sort_unstable_by(|(f1, _), (f2, _)| f1.total_cmp(f2));
+        Ok(Self { points })
+    }
+
+    /// Schedule through exponents at evenly spaced fractions of the days
+    pub fn evenly_spaced(exponents: &[f32]) -> Self {
+        let last = exponents.len().saturating_sub(1).max(1) as f32;
+        let points = exponents
+            .iter()
+            .enumerate()
+            .map(|(index, exp)| (index as f32 / last, *exp))
+            .collect();
+        Self { points }
+    }
+
+    pub fn exponent(&self, elapsed: f32) -> f32 {
+        let next = self
+            .points
+            .iter()
+            .position(|(fraction, _)| *fraction > elapsed);
+        match next {
+            Some(0) => self.points[0].1,
+            Some(index) => {
+                let (f1, e1) = self.points[index - 1];
+                let (f2, e2) = self.points[index];
+                e1 + (e2 - e1) * (elapsed - f1) / (f2 - f1)
+            }
+            None => self.points.last().unwrap().1,
+        }
+    }
+}
+
+impl FromStr for ExponentSchedule {
+    type Err = String;
+
+    /// Parses `fraction:exponent` control points separated by commas
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let points = s
+            .split(',')
+            .map(|point| {
+                let mut parts = point.splitn(2, ':');
+                let mut next_value = || {
+                    let value = parts.next().ok_or_else(|| {
+                        format!("Invalid control point: {}", point)
+                    })?;
+                    value
+                        .parse::<f32>()
+                        .map_err(|err| format!("{}: {}", err, value))
+                };
+                Ok((next_value()?, next_value()?))
+            })
+            .collect::<Result<_, String>>()?;
+        Self::new(points)
+    }
+}
+
+impl Display for ExponentSchedule {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let points = self
+            .points
+            .iter()
+            .map(|(fraction, exp)| format!("{:0.2}:{:0.4}", fraction, exp))
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(f, "{}", points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_control_points() {
+        let schedule = "1:2,0.5:1,0.25:1".parse::<ExponentSchedule>().unwrap();
+        assert_eq!(schedule.to_string(), "0.25:1.0000,0.50:1.0000,1.00:2.0000");
+        assert_eq!(schedule.exponent(0_f32), 1_f32);
+        assert_eq!(schedule.exponent(0.5_f32), 1_f32);
+        assert_eq!(schedule.exponent(0.75_f32), 1.5_f32);
+        assert_eq!(schedule.exponent(1_f32), 2_f32);
+    }
+
+    #[test]
+    fn spaces_exponents_evenly() {
+        let schedule = ExponentSchedule::evenly_spaced(&[0_f32, 1_f32, 3_f32]);
+        assert_eq!(schedule.exponent(0.25_f32), 0.5_f32);
+        assert_eq!(schedule.exponent(0.75_f32), 2_f32);
+        let constant = ExponentSchedule::evenly_spaced(&[2_f32]);
+        assert_eq!(constant.exponent(0.5_f32), 2_f32);
+    }
+
+    #[test]
+    fn rejects_invalid_schedules() {
+        for input in ["", "0.5", "1.5:1", "0:x"] {
+            assert!(input.parse::<ExponentSchedule>().is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::compact::CompactTask;
+use crate::Library;
+
+/// Names of the library scorers, as accepted by `scorer_by_name`
+pub const SCORER_NAMES: [&str; 4] =
+    ["sum", "throughput", "rarity", "pending-rarity"];
+
+/// What a scorer knows about a pending library at a step of the planner
+pub struct LibraryState<'a> {
+    pub library: &'a Library,
+    /// Books held by the library that are not scanned yet, best first
+    pub books: &'a [u32],
+    pub days_left: u64,
+    /// Number of pending libraries, this one included, that can still finish
+    /// signing up and hold every book not scanned yet, indexed by book id
+    pub holders: &'a [u32],
+    pub task: &'a CompactTask,
+}
+
+impl LibraryState<'_> {
+    /// Number of books the library could scan if signed up now
+    pub fn max_scans(&self) -> usize {
+        if self.days_left > self.library.signup_days {
+            ((self.days_left - self.library.signup_days)
+                * self.library.scan_rate) as usize
+        } else {
+            0
+        }
+    }
+
+    /// Books the library would scan if signed up now
+    pub fn scanned_books(&self) -> &[u32] {
+        &self.books[..self.max_scans().min(self.books.len())]
+    }
+
+    /// Days the library would sit idle after scanning all its books
+    pub fn idle_days(&self) -> u64 {
+        let max_scans = self.max_scans();
+        if max_scans > self.books.len() {
+            let scan_days = (self.books.len() as f32
+                / self.library.scan_rate as f32)
+                .ceil() as u64;
+            self.days_left - self.library.signup_days - scan_days
+        } else {
+            0
+        }
+    }
+
+    /// Divides a value by `signup_days^signup_exp`, and by
+    /// `idle_days^idle_exp` unless the library would never be idle
+    pub fn penalise(&self, value: f32, signup_exp: f32, idle_exp: f32) -> f32 {
+        let mut value =
+            value / (self.library.signup_days as f32).powf(signup_exp);
+        if idle_exp == 0_f32 {
+            return value;
+        }
+        let idle_days = self.idle_days();
+        if idle_days > 0 {
+            value /= (idle_days as f32).powf(idle_exp);
+        }
+        value
+    }
+}
+
+/// Priority of a pending library in the greedy planner: at every step, the
+/// library with the highest positive score is signed up next.
+pub trait LibraryScorer: Sync {
+    fn score(
+        &self,
+        state: &LibraryState,
+        signup_exp: f32,
+        idle_exp: f32,
+    ) -> f32;
+
+    /// Whether, with no idle exponent, scores never go up as days pass and
+    /// books get scanned, so that the planner may evaluate them lazily
+    fn is_monotone(&self) -> bool {
+        false
+    }
+}
+
+/// Total score of the books the library would scan
+pub struct SumScorer;
+
+impl LibraryScorer for SumScorer {
+    fn score(
+        &self,
+        state: &LibraryState,
+        signup_exp: f32,
+        idle_exp: f32,
+    ) -> f32 {
+        if state.library.signup_days >= state.days_left {
+            return 0_f32;
+        }
+        let sum = state
+            .scanned_books()
+            .iter()
+            .map(|book_id| state.task.book_score(*book_id))
+            .sum::<u64>() as f32;
+        if sum == 0_f32 {
+            return 0_f32;
+        }
+        state.penalise(sum, signup_exp, idle_exp)
+    }
+
+    /// The books the library would scan only ever get fewer and are never
+    /// replaced by better ones, so their sum never goes up
+    fn is_monotone(&self) -> bool {
+        true
+    }
+}
+
+/// Score the library adds per day of scanning, that is the average score of
+/// the books it would scan weighted by its scan rate, per sign-up day. Fast
+/// libraries with good books are preferred over large slow ones.
+pub struct ThroughputScorer;
+
+impl LibraryScorer for ThroughputScorer {
+    fn score(
+        &self,
+        state: &LibraryState,
+        signup_exp: f32,
+        idle_exp: f32,
+    ) -> f32 {
+        let books = state.scanned_books();
+        if books.is_empty() {
+            return 0_f32;
+        }
+        let sum = books
+            .iter()
+            .map(|book_id| state.task.book_score(*book_id))
+            .sum::<u64>() as f32;
+        let scan_days = (books.len() as f32 / state.library.scan_rate as f32)
+            .ceil()
+            .max(1_f32);
+        state.penalise(sum / scan_days, signup_exp, idle_exp)
+    }
+
+    /// Scanning the best books elsewhere can leave the library with fewer
+    /// scan days for the same rate, and so raise its average per day
+    fn is_monotone(&self) -> bool {
+        false
+    }
+}
+
+/// Total score of the books the library would scan, each divided by the
+/// number of libraries holding it, so that books that can be got elsewhere
+/// count for less
+pub struct RarityScorer;
+
+impl LibraryScorer for RarityScorer {
+    fn score(
+        &self,
+        state: &LibraryState,
+        signup_exp: f32,
+        idle_exp: f32,
+    ) -> f32 {
+        let sum = state
+            .scanned_books()
+            .iter()
+            .map(|book_id| {
+                let holders = state.task.book_libraries(*book_id).len();
+                state.task.book_score(*book_id) as f32 / holders.max(1) as f32
+            })
+            .sum::<f32>();
+        if sum == 0_f32 {
+            return 0_f32;
+        }
+        state.penalise(sum, signup_exp, idle_exp)
+    }
+
+    /// Like `SumScorer`, as the number of libraries holding a book is fixed
+    fn is_monotone(&self) -> bool {
+        true
+    }
+}
+
+/// Total score of the books the library would scan, each divided by the
+/// number of pending libraries that still hold it. Unlike `RarityScorer`,
+/// libraries that are signed up no longer count, so a book gets its full
+/// score back once its last other holder is gone.
+pub struct PendingRarityScorer;
+
+impl LibraryScorer for PendingRarityScorer {
+    fn score(
+        &self,
+        state: &LibraryState,
+        signup_exp: f32,
+        idle_exp: f32,
+    ) -> f32 {
+        let sum = state
+            .scanned_books()
+            .iter()
+            .map(|book_id| {
+                let holders = state.holders[*book_id as usize];
+                state.task.book_score(*book_id) as f32 / holders.max(1) as f32
+            })
+            .sum::<f32>();
+        if sum == 0_f32 {
+            return 0_f32;
+        }
+        state.penalise(sum, signup_exp, idle_exp)
+    }
+
+    /// Holders are released as libraries are signed up or run out of days,
+    /// which raises the share of the books of the ones left
+    fn is_monotone(&self) -> bool {
+        false
+    }
+}
+
+pub fn scorer_by_name(
+    name: &str,
+) -> Result<&'static dyn LibraryScorer, String> {
+    match name {
+        "sum" => Ok(&SumScorer),
+        "throughput" => Ok(&ThroughputScorer),
+        "rarity" => Ok(&RarityScorer),
+        "pending-rarity" => Ok(&PendingRarityScorer),
+        _ => Err(format!(
+            "Invalid scorer: {} (expected one of {})",
+            name,
+            SCORER_NAMES.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::example_task;
+    use crate::ScanningTask;
+
+    fn state<'a>(
+        task: &'a ScanningTask,
+        days_left: u64,
+        holders: &'a [u32],
+    ) -> LibraryState<'a> {
+        let library = &task.libraries[0];
+        LibraryState {
+            library,
+            books: &library.books,
+            days_left,
+            holders,
+            task: task.compact(),
+        }
+    }
+
+    #[test]
+    fn describes_library_state() {
+        let task = example_task();
+        let holders = task.book_library_counts();
+        let state = state(&task, 7, &holders);
+        assert_eq!(state.max_scans(), 10);
+        assert_eq!(state.scanned_books(), &[3, 4, 2, 1, 0]);
+        assert_eq!(state.idle_days(), 2);
+        assert_eq!(state.penalise(8_f32, 1_f32, 0_f32), 4_f32);
+        assert_eq!(state.penalise(8_f32, 1_f32, 1_f32), 2_f32);
+    }
+
+    #[test]
+    fn scores_example_library() {
+        let task = example_task();
+        let holders = task.book_library_counts();
+        let state = state(&task, 7, &holders);
+        let score =
+            |scorer: &dyn LibraryScorer| scorer.score(&state, 1_f32, 0_f32);
+        assert_eq!(score(&SumScorer), 8.5_f32);
+        assert_eq!(score(&ThroughputScorer), 17_f32 / 3_f32 / 2_f32);
+        assert_eq!(score(&RarityScorer), 6_f32);
+    }
+
+    #[test]
+    fn shares_books_among_pending_holders() {
+        let task = example_task();
+        let holders = task.book_library_counts();
+        let state = state(&task, 7, &holders);
+        assert_eq!(PendingRarityScorer.score(&state, 1_f32, 0_f32), 6_f32);
+        let released = vec![1; task.num_books()];
+        let state = LibraryState {
+            holders: &released,
+            ..state
+        };
+        assert_eq!(PendingRarityScorer.score(&state, 1_f32, 0_f32), 8.5_f32);
+    }
+
+    #[test]
+    fn scores_nothing_without_days_to_scan() {
+        let task = example_task();
+        let holders = task.book_library_counts();
+        for name in SCORER_NAMES.iter() {
+            let scorer = scorer_by_name(name).unwrap();
+            for days_left in [0, 1, 2] {
+                let state = state(&task, days_left, &holders);
+                assert_eq!(scorer.score(&state, 1_f32, 0_f32), 0_f32);
+            }
+        }
+        assert!(scorer_by_name("other").is_err());
+    }
+}
@@ -0,0 +1,432 @@
+use super::schedule::ExponentSchedule;
+use super::scoring::LibraryScorer;
+use super::{
+    build_best, Construction, PlanBuilder, ScanningPlan, SignupExponent,
+};
+use crate::ScanningTask;
+use num_format::{Locale, ToFormattedString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A heuristic parameter of the greedy planner. Parameters that are not
+/// swept keep their default value: 0 for the idle exponent and 1 for the
+/// sign-up exponent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parameter {
+    IdleExp,
+    SignupExp,
+    /// Sign-up exponent at a control point of a schedule. Control points are
+    /// evenly spaced over the days, a missing one has exponent 1, and a
+    /// schedule takes precedence over a constant sign-up exponent.
+    ControlPoint(usize),
+}
+
+impl FromStr for Parameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "idle" => Ok(Parameter::IdleExp),
+            "signup" => Ok(Parameter::SignupExp),
+            _ => s
+                .strip_prefix("point")
+                .and_then(|index| index.parse().ok())
+                .map(Parameter::ControlPoint)
+                .ok_or_else(|| format!("Invalid parameter: {}", s)),
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Parameter::IdleExp => write!(f, "idle"),
+            Parameter::SignupExp => write!(f, "signup"),
+            Parameter::ControlPoint(index) => write!(f, "point{}", index),
+        }
+    }
+}
+
+/// Values a parameter is swept over: `min` to `max` in increments of `step`.
+/// Random search samples the whole interval, and adaptive search refines its
+/// steps down to `step`.
+#[derive(Clone, Debug)]
+pub struct ParameterRange {
+    pub parameter: Parameter,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl FromStr for ParameterRange {
+    type Err = String;
+
+    /// Parses `name=min,max,step`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '=');
+        let parameter = parts.next().unwrap_or_default().parse()?;
+        let values = parts
+            .next()
+            .ok_or_else(|| format!("Missing parameter values: {}", s))?
+            .split(',')
+            .map(|value| {
+                value
+                    .parse::<f32>()
+                    .map_err(|err| format!("{}: {}", err, value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match values.as_slice() {
+            [min, max, step] if min <= max && *step > 0_f32 => Ok(Self {
+                parameter,
+                min: *min,
+                max: *max,
+                step: *step,
+            }),
+            _ => Err(format!("Invalid parameter range: {}", s)),
+        }
+    }
+}
+
+impl ParameterRange {
+    fn grid_values(&self) -> Vec<f32> {
+        let mut values = Vec::new();
+        let mut value = self.min;
+        while value <= self.max {
+            values.push(value);
+            value += self.step;
+        }
+        values
+    }
+}
+
+pub enum SweepStrategy {
+    /// Every combination of the parameter grid values
+    Grid,
+    /// A number of combinations sampled uniformly at random
+    Random(usize),
+    /// Pattern search from the middle of the ranges, with a budget of plans:
+    /// moves to the best neighbouring combination and halves the steps when
+    /// no neighbour is better
+    Adaptive(usize),
+}
+
+impl FromStr for SweepStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let name = parts.next().unwrap_or_default();
+        let count = parts
+            .next()
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .map_err(|err| format!("{}: {}", err, value))
+            })
+            .transpose()?;
+        match (name, count) {
+            ("grid", None) => Ok(SweepStrategy::Grid),
+            ("random", Some(count)) => Ok(SweepStrategy::Random(count)),
+            ("adaptive", Some(count)) => Ok(SweepStrategy::Adaptive(count)),
+            _ => Err(format!("Invalid sweep strategy: {}", s)),
+        }
+    }
+}
+
+/// Searches the joint space of planner parameters for the combination that
+/// yields the best plan. Combinations are evaluated in parallel.
+pub struct ParameterSweep<'a> {
+    task: &'a ScanningTask,
+    ranges: Vec<ParameterRange>,
+    strategy: SweepStrategy,
+    scorer: &'a dyn LibraryScorer,
+    seed: u64,
+}
+
+impl<'a> ParameterSweep<'a> {
+    pub fn new(
+        task: &'a ScanningTask,
+        ranges: Vec<ParameterRange>,
+        strategy: SweepStrategy,
+        scorer: &'a dyn LibraryScorer,
+        seed: u64,
+    ) -> Self {
+        Self {
+            task,
+            ranges,
+            strategy,
+            scorer,
+            seed,
+        }
+    }
+
+    /// Runs the sweep, prints the score of every combination evaluated and
+    /// returns the best plan.
+    pub fn run(&self) -> ScanningPlan<'a> {
+        let mut results = Vec::new();
+        let best_plan = match self.strategy {
+            SweepStrategy::Grid => {
+                let mut combinations = vec![Vec::new()];
+                for range in self.ranges.iter() {
+                    combinations = combinations
+                        .into_iter()
+                        .flat_map(|combination: Vec<f32>| {
+                            range.grid_values().into_iter().map(move |value| {
+                                let mut combination = combination.clone();
+                                combination.push(value);
+                                combination
+                            })
+                        })
+                        .collect();
+                }
+                self.evaluate(combinations, &mut results)
+            }
+            SweepStrategy::Random(count) => {
+                let mut rng = StdRng::seed_from_u64(self.seed);
+                let combinations = (0..count)
+                    .map(|_| {
+                        self.ranges
+                            .iter()
+                            .map(|range| {
+                                if range.min < range.max {
+                                    rng.gen_range(range.min, range.max)
+                                } else {
+                                    range.min
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect();
+                self.evaluate(combinations, &mut results)
+            }
+            SweepStrategy::Adaptive(count) => {
+                self.adaptive_search(count, &mut results)
+            }
+        };
+        self.print_results(&results);
+        best_plan
+    }
+
+    fn adaptive_search(
+        &self,
+        count: usize,
+        results: &mut Vec<(Vec<f32>, Option<u64>)>,
+    ) -> ScanningPlan<'a> {
+        let mut current = self
+            .ranges
+            .iter()
+            .map(|range| (range.min + range.max) / 2_f32)
+            .collect::<Vec<_>>();
+        let mut steps = self
+            .ranges
+            .iter()
+            .map(|range| ((range.max - range.min) / 4_f32).max(range.step))
+            .collect::<Vec<_>>();
+        let mut best_plan = self.evaluate(vec![current.clone()], results);
+        let mut best_score = results[0].1.unwrap_or_default();
+
+        while results.len() < count {
+            let mut neighbours = Vec::new();
+            for (index, range) in self.ranges.iter().enumerate() {
+                for delta in [-steps[index], steps[index]].iter() {
+                    let mut neighbour = current.clone();
+                    neighbour[index] = (neighbour[index] + delta)
+                        .max(range.min)
+                        .min(range.max);
+                    if !neighbours.contains(&neighbour)
+                        && results
+                            .iter()
+                            .all(|(values, _)| *values != neighbour)
+                    {
+                        neighbours.push(neighbour);
+                    }
+                }
+            }
+            neighbours.truncate(count - results.len());
+
+            let start = results.len();
+            let plan = self.evaluate(neighbours, results);
+            let mut best_neighbour: Option<(u64, &Vec<f32>)> = None;
+            for (values, score) in results[start..].iter() {
+                if let Some(score) = score {
+                    if best_neighbour.is_none_or(|(best, _)| *score > best) {
+                        best_neighbour = Some((*score, values));
+                    }
+                }
+            }
+            match best_neighbour {
+                Some((score, values)) if score > best_score => {
+                    best_score = score;
+                    current = values.clone();
+                    best_plan = plan;
+                }
+                _ => {
+                    if steps
+                        .iter()
+                        .zip(self.ranges.iter())
+                        .all(|(step, range)| *step <= range.step)
+                    {
+                        break;
+                    }
+                    for (step, range) in
+                        steps.iter_mut().zip(self.ranges.iter())
+                    {
+                        *step = (*step / 2_f32).max(range.step);
+                    }
+                }
+            }
+        }
+        best_plan
+    }
+
+    /// Builds the plans of a batch of combinations in parallel, appends their
+    /// scores to the results and returns the best plan of the batch.
+    fn evaluate(
+        &self,
+        combinations: Vec<Vec<f32>>,
+        results: &mut Vec<(Vec<f32>, Option<u64>)>,
+    ) -> ScanningPlan<'a> {
+        let (scores, _, best_plan) =
+            build_best(self.task, combinations.len(), |index| {
+                self.build_plan(&combinations[index])
+            });
+        results.extend(combinations.into_iter().zip(scores));
+        best_plan
+    }
+
+    fn build_plan(&self, values: &[f32]) -> ScanningPlan<'a> {
+        let mut idle_exp = 0_f32;
+        let mut signup_exp = 1_f32;
+        let mut control_points = Vec::new();
+        for (range, value) in self.ranges.iter().zip(values.iter()) {
+            match range.parameter {
+                Parameter::IdleExp => idle_exp = *value,
+                Parameter::SignupExp => signup_exp = *value,
+                Parameter::ControlPoint(index) => {
+                    if control_points.len() <= index {
+                        control_points.resize(index + 1, 1_f32);
+                    }
+                    control_points[index] = *value;
+                }
+            }
+        }
+        let builder = PlanBuilder::new(
+            self.task,
+            idle_exp,
+            SignupExponent::Fixed(signup_exp),
+            self.scorer,
+            Construction::Greedy,
+            self.seed,
+        );
+        if control_points.is_empty() {
+            builder.build_fixed_plan(signup_exp)
+        } else {
+            let schedule = ExponentSchedule::evenly_spaced(&control_points);
+            builder.build_schedule_plan(&schedule)
+        }
+    }
+
+    fn print_results(&self, results: &[(Vec<f32>, Option<u64>)]) {
+        let header = self
+            .ranges
+            .iter()
+            .map(|range| format!("{:>10}", range.parameter.to_string()))
+            .collect::<String>();
+        println!("{} {:>12}", header, "score");
+        let mut best: Option<&(Vec<f32>, Option<u64>)> = None;
+        for result in results.iter() {
+            let (values, score) = result;
+            let row = values
+                .iter()
+                .map(|value| format!("{:>10.4}", value))
+                .collect::<String>();
+            let score = score.map_or("invalid".to_string(), |score| {
+                score.to_formatted_string(&Locale::en)
+            });
+            println!("{} {:>12}", row, score);
+            if best.is_none_or(|(_, best)| result.1 > *best) {
+                best = Some(result);
+            }
+        }
+        if let Some((values, Some(score))) = best {
+            let combination = self
+                .ranges
+                .iter()
+                .zip(values.iter())
+                .map(|(range, value)| {
+                    format!("{} {:0.4}", range.parameter, value)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "Best combination: {}, score {}",
+                combination,
+                score.to_formatted_string(&Locale::en)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::example_task;
+    use crate::planner::scoring::SumScorer;
+
+    #[test]
+    fn parses_parameters() {
+        for name in ["idle", "signup", "point3"] {
+            assert_eq!(name.parse::<Parameter>().unwrap().to_string(), name);
+        }
+        assert!("point".parse::<Parameter>().is_err());
+        assert!("other".parse::<Parameter>().is_err());
+    }
+
+    #[test]
+    fn parses_ranges_and_grid_values() {
+        let range = "signup=0.5,1.5,0.5".parse::<ParameterRange>().unwrap();
+        assert_eq!(range.parameter, Parameter::SignupExp);
+        assert_eq!(range.grid_values(), vec![0.5_f32, 1_f32, 1.5_f32]);
+        for input in ["signup", "signup=1,0,1", "signup=0,1,0", "idle=0,1"] {
+            assert!(input.parse::<ParameterRange>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parses_strategies() {
+        assert!(matches!("grid".parse(), Ok(SweepStrategy::Grid)));
+        assert!(matches!("random:5".parse(), Ok(SweepStrategy::Random(5))));
+        assert!(matches!(
+            "adaptive:9".parse(),
+            Ok(SweepStrategy::Adaptive(9))
+        ));
+        assert!("grid:2".parse::<SweepStrategy>().is_err());
+        assert!("random".parse::<SweepStrategy>().is_err());
+    }
+
+    #[test]
+    fn sweeps_example() {
+        let task = example_task();
+        let ranges = vec![
+            "idle=0,1,1".parse().unwrap(),
+            "signup=0,2,1".parse().unwrap(),
+        ];
+        for strategy in [
+            SweepStrategy::Grid,
+            SweepStrategy::Random(4),
+            SweepStrategy::Adaptive(6),
+        ] {
+            let sweep = ParameterSweep::new(
+                &task,
+                ranges.clone(),
+                strategy,
+                &SumScorer,
+                0,
+            );
+            let plan = sweep.run();
+            assert_eq!(plan.score().map(|(score, _, _)| score), Ok(21));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::compact::BookSet;
+use super::planner::ScanningPlan;
+use super::{Library, ScanningTask};
+use std::collections::HashSet;
+
+/// Turns any plan into a valid one. Libraries that are signed up more than
+/// once or that cannot finish signing up before the deadline are dropped, and
+/// every remaining library keeps its highest-scoring listed books that it
+/// actually holds, up to its capacity. Capacity freed up along the way is
+/// filled with the best books no other library scans, and the days left at
+/// the end with the libraries that add the most score per sign-up day.
+pub fn repair<'a>(plan: &ScanningPlan<'a>) -> ScanningPlan<'a> {
+    let task = plan.task();
+    let compact = task.compact();
+    let mut days_left = task.days;
+    let mut signedup_libraries = HashSet::new();
+    let mut scanned = compact.new_book_set();
+    let mut held = compact.new_book_set();
+    let mut entries: Vec<(&Library, usize, Vec<u32>)> = Vec::new();
+    for (library, books) in plan.libraries() {
+        if library.signup_days >= days_left
+            || !signedup_libraries.insert(library.id)
+        {
+            continue;
+        }
+        days_left -= library.signup_days;
+        let max_scans = (days_left * library.scan_rate) as usize;
+        for book_id in library.books.iter() {
+            held.insert(*book_id);
+        }
+        let mut kept = books
+            .iter()
+            .filter(|book_id| {
+                held.contains(**book_id) && !scanned.contains(**book_id)
+            })
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        for book_id in library.books.iter() {
+            held.remove(*book_id);
+        }
+        compact.sort_books(&mut kept);
+        kept.truncate(max_scans);
+        for book_id in kept.iter() {
+            scanned.insert(*book_id);
+        }
+        entries.push((library, max_scans, kept));
+    }
+
+    // Reuse spare capacity for books that no library scans yet
+    for (library, max_scans, books) in entries.iter_mut() {
+        if books.len() >= *max_scans {
+            continue;
+        }
+        let spare = library
+            .books
+            .iter()
+            .filter(|book_id| !scanned.contains(**book_id))
+            .take(*max_scans - books.len())
+            .cloned()
+            .collect::<Vec<_>>();
+        for book_id in spare.iter() {
+            scanned.insert(*book_id);
+        }
+        books.extend(spare);
+    }
+
+    // Sign up more libraries in the days that are left
+    while let Some((library, books)) =
+        best_unused_library(task, &signedup_libraries, &scanned, days_left)
+    {
+        signedup_libraries.insert(library.id);
+        days_left -= library.signup_days;
+        for book_id in books.iter() {
+            scanned.insert(*book_id);
+        }
+        entries.push((library, books.len(), books));
+    }
+
+    let mut repaired = ScanningPlan::new(task);
+    for (library, _, books) in entries {
+        if !books.is_empty() {
+            repaired.add_library(library, books);
+        }
+    }
+    repaired
+}
+
+/// Library not signed up yet that adds the most score per sign-up day with
+/// `days_left` days left, along with the books it would scan
+fn best_unused_library<'a>(
+    task: &'a ScanningTask,
+    signedup_libraries: &HashSet<u32>,
+    scanned: &BookSet,
+    days_left: u64,
+) -> Option<(&'a Library, Vec<u32>)> {
+    let compact = task.compact();
+    let mut best: Option<(f32, &Library, Vec<u32>)> = None;
+    for library in task.libraries.iter() {
+        if library.signup_days >= days_left
+            || signedup_libraries.contains(&library.id)
+        {
+            continue;
+        }
+        let max_scans = (days_left - library.signup_days) * library.scan_rate;
+        let books = library
+            .books
+            .iter()
+            .filter(|book_id| !scanned.contains(**book_id))
+            .take(max_scans as usize)
+            .cloned()
+            .collect::<Vec<_>>();
+        let score = books
+            .iter()
+            .map(|book_id| compact.book_score(*book_id))
+            .sum::<u64>();
+        let value = score as f32 / library.signup_days as f32;
+        if score > 0 && best.as_ref().is_none_or(|(best, _, _)| value > *best) {
+            best = Some((value, library, books));
+        }
+    }
+    best.map(|(_, library, books)| (library, books))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, EXAMPLE_SUBMISSION};
+    use crate::validator::validate;
+
+    #[test]
+    fn repairs_invalid_submission() {
+        let task = example_task();
+        let input = "3\n0 2\n9 9 4\n0 1\n1\nfoo\n";
+        let repaired = repair(&ScanningPlan::parse_lenient(&task, input));
+        assert!(validate(&task, &repaired.to_string()).is_empty());
+        assert_eq!(repaired.count_signedup_libraries(), 2);
+        assert_eq!(repaired.judge_score(), (21, 0));
+    }
+
+    #[test]
+    fn keeps_valid_submission() {
+        let task = example_task();
+        let plan = ScanningPlan::parse(&task, EXAMPLE_SUBMISSION).unwrap();
+        assert_eq!(repair(&plan).to_string(), EXAMPLE_SUBMISSION);
+    }
+}
@@ -0,0 +1,343 @@
+use super::planner::ScanningPlan;
+use super::{Library, ScanningTask};
+use num_format::{Locale, ToFormattedString};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const NO_OWNER: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveKind {
+    Swap,
+    Insert,
+    Remove,
+    Add,
+}
+
+pub const ALL_MOVES: [MoveKind; 4] = [
+    MoveKind::Swap,
+    MoveKind::Insert,
+    MoveKind::Remove,
+    MoveKind::Add,
+];
+
+impl FromStr for MoveKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "swap" => Ok(MoveKind::Swap),
+            "insert" => Ok(MoveKind::Insert),
+            "remove" => Ok(MoveKind::Remove),
+            "add" => Ok(MoveKind::Add),
+            _ => Err(format!("Invalid move type: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Move {
+    /// Swap the libraries at two positions
+    Swap(usize, usize),
+    /// Move the library at the first position to the second position
+    Insert(usize, usize),
+    /// Remove the library at a position
+    Remove(usize),
+    /// Insert an unused library (by index) at a position
+    Add(usize, usize),
+}
+
+/// A plan represented as a sequence of signed-up libraries, where every
+/// library scans its best books not scanned by an earlier library. Moves are
+/// evaluated incrementally: only the libraries from the first position a move
+/// touches onwards are decoded again.
+pub struct LibraryOrder<'a> {
+    task: &'a ScanningTask,
+    libraries: &'a [Library],
+    order: Vec<u32>,
+    unused: Vec<u32>,
+    assigned: Vec<Vec<u32>>,
+    prefix_days: Vec<u64>,
+    prefix_scores: Vec<u64>,
+    owners: Vec<u32>,
+    marks: Vec<u64>,
+    stamp: u64,
+}
+
+impl<'a> LibraryOrder<'a> {
+    pub fn new(plan: &ScanningPlan<'a>) -> Self {
+        let order = plan.libraries().map(|(library, _)| library.id).collect();
+        Self::from_order(plan.task(), order)
+    }
+
+    /// Builds the order of a sequence of library ids. Repeated libraries
+    /// are only signed up at their first position.
+    pub fn from_order(task: &'a ScanningTask, mut order: Vec<u32>) -> Self {
+        let libraries = &task.libraries;
+
+        let mut in_order = vec![false; libraries.len()];
+        order
+            .retain(|id| !std::mem::replace(&mut in_order[*id as usize], true));
+        let unused = (0..libraries.len() as u32)
+            .filter(|id| !in_order[*id as usize])
+            .collect();
+
+        let mut library_order = Self {
+            task,
+            libraries,
+            order: Vec::new(),
+            unused,
+            assigned: Vec::new(),
+            prefix_days: vec![0],
+            prefix_scores: vec![0],
+            owners: vec![NO_OWNER; task.num_books()],
+            marks: vec![0; task.num_books()],
+            stamp: 0,
+        };
+        library_order.decode(0, order, true);
+        library_order
+    }
+
+    pub fn score(&self) -> u64 {
+        *self.prefix_scores.last().unwrap()
+    }
+
+    pub fn order(&self) -> &[u32] {
+        &self.order
+    }
+
+    pub fn random_move<R: Rng>(
+        &self,
+        rng: &mut R,
+        kinds: &[MoveKind],
+    ) -> Option<Move> {
+        let len = self.order.len();
+        let kind = kinds[rng.gen_range(0, kinds.len())];
+        match kind {
+            MoveKind::Swap | MoveKind::Insert if len < 2 => None,
+            MoveKind::Swap => {
+                let first = rng.gen_range(0, len - 1);
+                Some(Move::Swap(first, rng.gen_range(first + 1, len)))
+            }
+            MoveKind::Insert => {
+                let from = rng.gen_range(0, len);
+                let to = rng.gen_range(0, len - 1);
+                Some(Move::Insert(from, if to >= from { to + 1 } else { to }))
+            }
+            MoveKind::Remove if len == 0 => None,
+            MoveKind::Remove => Some(Move::Remove(rng.gen_range(0, len))),
+            MoveKind::Add if self.unused.is_empty() => None,
+            MoveKind::Add => Some(Move::Add(
+                rng.gen_range(0, self.unused.len()),
+                rng.gen_range(0, len + 1),
+            )),
+        }
+    }
+
+    /// Returns the score the plan would have after applying a move.
+    pub fn evaluate(&mut self, mv: Move) -> u64 {
+        let (start, suffix) = self.suffix(mv);
+        self.prefix_scores[start] + self.decode(start, suffix, false)
+    }
+
+    pub fn apply(&mut self, mv: Move) {
+        let (start, suffix) = self.suffix(mv);
+        match mv {
+            Move::Remove(pos) => self.unused.push(self.order[pos]),
+            Move::Add(index, _) => {
+                self.unused.swap_remove(index);
+            }
+            _ => (),
+        }
+        self.decode(start, suffix, true);
+    }
+
+    pub fn to_plan(&self) -> ScanningPlan<'a> {
+        let mut plan = ScanningPlan::new(self.task);
+        for (id, books) in self.order.iter().zip(self.assigned.iter()) {
+            if books.is_empty() {
+                continue;
+            }
+            plan.add_library(&self.libraries[*id as usize], books.clone());
+        }
+        plan
+    }
+
+    fn suffix(&self, mv: Move) -> (usize, Vec<u32>) {
+        match mv {
+            Move::Swap(first, second) => {
+                let mut suffix = self.order[first..].to_vec();
+                suffix.swap(0, second - first);
+                (first, suffix)
+            }
+            Move::Insert(from, to) => {
+                let start = from.min(to);
+                let mut suffix = self.order[start..].to_vec();
+                let id = suffix.remove(from - start);
+                suffix.insert(to - start, id);
+                (start, suffix)
+            }
+            Move::Remove(pos) => (pos, self.order[pos + 1..].to_vec()),
+            Move::Add(index, pos) => {
+                let mut suffix = Vec::with_capacity(self.order.len() - pos + 1);
+                suffix.push(self.unused[index]);
+                suffix.extend_from_slice(&self.order[pos..]);
+                (pos, suffix)
+            }
+        }
+    }
+
+    /// Decodes the libraries from position `start` onwards, assuming the ones
+    /// before it are unchanged, and returns the score of the decoded suffix.
+    /// With `commit` set, the suffix replaces the current one.
+    fn decode(&mut self, start: usize, suffix: Vec<u32>, commit: bool) -> u64 {
+        if commit {
+            for books in self.assigned.drain(start..) {
+                for book_id in books {
+                    self.owners[book_id as usize] = NO_OWNER;
+                }
+            }
+            self.order.truncate(start);
+            self.prefix_days.truncate(start + 1);
+            self.prefix_scores.truncate(start + 1);
+        }
+        self.stamp += 1;
+
+        let compact = self.task.compact();
+        let mut days = self.prefix_days[start];
+        let mut score = 0;
+        for (offset, id) in suffix.iter().enumerate() {
+            let pos = (start + offset) as u32;
+            let library = &self.libraries[*id as usize];
+            days += library.signup_days;
+            let mut scanned = Vec::new();
+            if days < self.task.days {
+                let mut max_scans = (self.task.days - days) * library.scan_rate;
+                for book_id in library.books.iter() {
+                    if max_scans == 0 {
+                        break;
+                    }
+                    let book = *book_id as usize;
+                    let owner = self.owners[book];
+                    if (owner != NO_OWNER && owner < start as u32)
+                        || self.marks[book] == self.stamp
+                    {
+                        continue;
+                    }
+                    self.marks[book] = self.stamp;
+                    score += compact.book_scores[book];
+                    max_scans -= 1;
+                    if commit {
+                        self.owners[book] = pos;
+                        scanned.push(*book_id);
+                    }
+                }
+            }
+            if commit {
+                self.order.push(*id);
+                self.assigned.push(scanned);
+                self.prefix_days.push(days);
+                self.prefix_scores.push(self.prefix_scores[start] + score);
+            }
+        }
+        score
+    }
+}
+
+pub struct LocalSearch {
+    iterations: usize,
+    time_limit: Option<Duration>,
+    seed: u64,
+}
+
+impl LocalSearch {
+    pub fn new(
+        iterations: usize,
+        time_limit: Option<Duration>,
+        seed: u64,
+    ) -> Self {
+        Self {
+            iterations,
+            time_limit,
+            seed,
+        }
+    }
+
+    /// Applies random swap, insert, remove and add moves to the library order
+    /// of a plan, keeping only those that improve its score.
+    pub fn improve<'a>(&self, plan: &ScanningPlan<'a>) -> ScanningPlan<'a> {
+        let mut order = LibraryOrder::new(plan);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let start_time = Instant::now();
+        let mut improvements = 0;
+        let mut iteration = 0;
+        while iteration < self.iterations {
+            if let Some(limit) = self.time_limit {
+                if start_time.elapsed() >= limit {
+                    break;
+                }
+            }
+            iteration += 1;
+            if let Some(mv) = order.random_move(&mut rng, &ALL_MOVES) {
+                if order.evaluate(mv) > order.score() {
+                    order.apply(mv);
+                    improvements += 1;
+                }
+            }
+        }
+        println!(
+            "Local search: {} improvements in {} iterations, score {}",
+            improvements.to_formatted_string(&Locale::en),
+            iteration.to_formatted_string(&Locale::en),
+            order.score().to_formatted_string(&Locale::en)
+        );
+
+        let improved = order.to_plan();
+        match plan.score() {
+            Ok((score, _, _)) if score >= order.score() => plan.clone(),
+            _ => improved,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::example_task;
+
+    #[test]
+    fn evaluates_moves_incrementally() {
+        let task = example_task();
+        let mut order = LibraryOrder::from_order(&task, vec![0]);
+        assert_eq!(order.score(), 17);
+        assert_eq!(order.evaluate(Move::Remove(0)), 0);
+        assert_eq!(order.evaluate(Move::Add(0, 0)), 21);
+        order.apply(Move::Add(0, 0));
+        assert_eq!(order.order(), &[1, 0]);
+        assert_eq!(order.score(), 21);
+        assert_eq!(order.evaluate(Move::Remove(0)), 17);
+        assert_eq!(order.evaluate(Move::Remove(1)), 14);
+        assert_eq!(order.evaluate(Move::Swap(0, 1)), 21);
+        order.apply(Move::Insert(1, 0));
+        assert_eq!(order.order(), &[0, 1]);
+        assert_eq!(order.to_plan().score().map(|(score, _, _)| score), Ok(21));
+    }
+
+    #[test]
+    fn signs_up_repeated_libraries_once() {
+        let task = example_task();
+        let order = LibraryOrder::from_order(&task, vec![1, 0, 1]);
+        assert_eq!(order.order(), &[1, 0]);
+        assert_eq!(order.score(), 21);
+    }
+
+    #[test]
+    fn improves_plan() {
+        let task = example_task();
+        let plan = LibraryOrder::from_order(&task, vec![0]).to_plan();
+        let improved = LocalSearch::new(100, None, 0).improve(&plan);
+        assert_eq!(improved.score().map(|(score, _, _)| score), Ok(21));
+    }
+}
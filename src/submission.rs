@@ -0,0 +1,41 @@
+use std::num::ParseIntError;
+
+/// A token of a submission that is not a number
+pub struct InvalidToken<'a> {
+    /// Position of the token on its line, from 0
+    pub position: usize,
+    pub token: &'a str,
+    pub error: ParseIntError,
+}
+
+/// A line of a submission, numbered from 1, with the numbers on it and the
+/// tokens that are not numbers
+pub struct Line<'a> {
+    pub number: usize,
+    pub values: Vec<u32>,
+    pub invalid: Vec<InvalidToken<'a>>,
+}
+
+/// Splits a submission into lines of numbers. Both the strict parser and the
+/// validator read submissions this way, so that they agree on every token.
+pub fn lines(input: &str) -> impl Iterator<Item = Line<'_>> {
+    input.lines().enumerate().map(|(index, line)| {
+        let mut values = Vec::new();
+        let mut invalid = Vec::new();
+        for (position, token) in line.split_whitespace().enumerate() {
+            match token.parse() {
+                Ok(value) => values.push(value),
+                Err(error) => invalid.push(InvalidToken {
+                    position,
+                    token,
+                    error,
+                }),
+            }
+        }
+        Line {
+            number: index + 1,
+            values,
+            invalid,
+        }
+    })
+}
@@ -0,0 +1,305 @@
+use super::submission;
+use super::ScanningTask;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The judge rejects the submission
+    Error,
+    /// The judge accepts the submission but ignores part of it
+    Warning,
+}
+
+pub struct Violation {
+    pub line: usize,
+    pub library: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(line: usize, library: Option<u32>, message: String) -> Self {
+        Self {
+            line,
+            library,
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(line: usize, library: Option<u32>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(line, library, message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self.severity {
+            Severity::Error => "",
+            Severity::Warning => "Warning: ",
+        };
+        match self.library {
+            Some(id) => {
+                write!(
+                    f,
+                    "Line {} (library {}): {}{}",
+                    self.line, id, prefix, self.message
+                )
+            }
+            None => {
+                write!(f, "Line {}: {}{}", self.line, prefix, self.message)
+            }
+        }
+    }
+}
+
+/// Checks a submission against every rule of the judge and returns all the
+/// violations found, in the order they appear in the submission. Libraries
+/// listing more books than they can scan are only warned about: the judge
+/// scans the books in the listed order and ignores the rest, as
+/// `ScanningPlan::judge_score` does.
+pub fn validate(task: &ScanningTask, input: &str) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut lines = submission::lines(input).collect::<Vec<_>>();
+    // Ignore trailing blank lines, as the parser does, but for the list of
+    // books of the last library, which may be blank
+    let is_blank = |line: &submission::Line<'_>| {
+        line.values.is_empty() && line.invalid.is_empty()
+    };
+    let mut end = lines.len();
+    while end > 0 && is_blank(&lines[end - 1]) {
+        end -= 1;
+    }
+    if end > 0 && end % 2 == 0 {
+        end = lines.len().min(end + 1);
+    }
+    lines.truncate(end);
+    let mut lines = lines.into_iter();
+    let mut next_values = |violations: &mut Vec<Violation>| {
+        lines.next().map(|line| {
+            for invalid in line.invalid.iter() {
+                violations.push(Violation::new(
+                    line.number,
+                    None,
+                    format!(
+                        "{} at position {}: {}",
+                        invalid.error, invalid.position, invalid.token
+                    ),
+                ));
+            }
+            (line.number, line.values)
+        })
+    };
+
+    let declared_count = match next_values(&mut violations) {
+        Some((line_num, values)) if values.len() != 1 => {
+            violations.push(Violation::new(
+                line_num,
+                None,
+                "Expected the number of libraries".to_string(),
+            ));
+            None
+        }
+        Some((_, values)) => Some(values[0] as usize),
+        None => {
+            violations.push(Violation::new(
+                1,
+                None,
+                "Empty submission".to_string(),
+            ));
+            return violations;
+        }
+    };
+
+    let mut entry_count = 0;
+    let mut days_left = task.days;
+    let mut signedup_libraries = HashSet::new();
+    let mut held = task.compact().new_book_set();
+    while let Some((line_num, values)) = next_values(&mut violations) {
+        entry_count += 1;
+        let library_id = values.first().cloned();
+        let (book_line_num, book_ids) = match next_values(&mut violations) {
+            Some(next) => next,
+            None => {
+                violations.push(Violation::new(
+                    line_num,
+                    library_id,
+                    "Missing list of books".to_string(),
+                ));
+                break;
+            }
+        };
+        if values.len() != 2 {
+            violations.push(Violation::new(
+                line_num,
+                library_id,
+                "Expected a library id and a number of books".to_string(),
+            ));
+        } else if book_ids.len() != values[1] as usize {
+            violations.push(Violation::new(
+                book_line_num,
+                library_id,
+                format!(
+                    "Declared {} books, listed {}",
+                    values[1],
+                    book_ids.len()
+                ),
+            ));
+        }
+        if book_ids.is_empty() {
+            violations.push(Violation::new(
+                book_line_num,
+                library_id,
+                "No books listed".to_string(),
+            ));
+        }
+
+        let library = match library_id {
+            Some(id) => match task.library(id) {
+                Some(library) => library,
+                None => {
+                    violations.push(Violation::new(
+                        line_num,
+                        library_id,
+                        "Invalid library id".to_string(),
+                    ));
+                    continue;
+                }
+            },
+            None => continue,
+        };
+        if !signedup_libraries.insert(library.id) {
+            violations.push(Violation::new(
+                line_num,
+                library_id,
+                "Library signed up more than once".to_string(),
+            ));
+        }
+
+        if library.signup_days > days_left {
+            violations.push(Violation::new(
+                line_num,
+                library_id,
+                format!(
+                    "Sign-up finishes on day {}, after the deadline",
+                    task.days - days_left + library.signup_days
+                ),
+            ));
+            days_left = 0;
+        } else {
+            days_left -= library.signup_days;
+            let max_scans = (days_left * library.scan_rate) as usize;
+            if book_ids.len() > max_scans {
+                violations.push(Violation::warning(
+                    book_line_num,
+                    library_id,
+                    format!(
+                        "Lists {} books but can only scan {}",
+                        book_ids.len(),
+                        max_scans
+                    ),
+                ));
+            }
+        }
+
+        for book_id in library.books.iter() {
+            held.insert(*book_id);
+        }
+        let mut listed_books = HashSet::new();
+        for (pos, book_id) in book_ids.iter().enumerate() {
+            let message = if task.book_score(*book_id).is_none() {
+                "does not exist"
+            } else if !held.contains(*book_id) {
+                "is not held by the library"
+            } else if !listed_books.insert(book_id) {
+                "is listed more than once"
+            } else {
+                continue;
+            };
+            violations.push(Violation::new(
+                book_line_num,
+                library_id,
+                format!("Book {} at position {} {}", book_id, pos, message),
+            ));
+        }
+        for book_id in library.books.iter() {
+            held.remove(*book_id);
+        }
+    }
+
+    if let Some(count) = declared_count {
+        if count != entry_count {
+            violations.push(Violation::new(
+                1,
+                None,
+                format!("Declared {} libraries, listed {}", count, entry_count),
+            ));
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{example_task, EXAMPLE_SUBMISSION};
+
+    #[test]
+    fn accepts_valid_submission() {
+        let task = example_task();
+        assert!(validate(&task, EXAMPLE_SUBMISSION).is_empty());
+    }
+
+    #[test]
+    fn ignores_trailing_blank_lines() {
+        let task = example_task();
+        let input = format!("{}\n\n", EXAMPLE_SUBMISSION);
+        assert!(validate(&task, &input).is_empty());
+        // A blank list of books is still read as the last one
+        let violations = validate(&task, "1\n0 0\n\n\n");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "No books listed");
+    }
+
+    #[test]
+    fn flags_duplicate_library() {
+        let task = example_task();
+        let violations = validate(&task, "2\n0 1\n0\n0 1\n1\n");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, 4);
+        assert_eq!(violations[0].library, Some(0));
+        assert!(violations[0].is_error());
+    }
+
+    #[test]
+    fn flags_every_violation_in_order() {
+        let task = example_task();
+        let violations = validate(&task, "3\n1 2\n1 1\n9 1\n0\n");
+        let lines = violations
+            .iter()
+            .map(|violation| violation.line)
+            .collect::<Vec<_>>();
+        // Book not held, twice, then invalid library and declared count
+        assert_eq!(lines, vec![3, 3, 4, 1]);
+        assert!(violations.iter().all(Violation::is_error));
+    }
+
+    #[test]
+    fn warns_about_scans_past_deadline() {
+        let task = example_task();
+        let input = "2\n1 4\n5 3 2 0\n0 5\n4 3 2 1 0\n";
+        let violations = validate(&task, input);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, 5);
+        assert_eq!(violations[0].severity, Severity::Warning);
+    }
+}